@@ -150,8 +150,8 @@ struct UnnafArgs {
     output: Option<String>,
     #[arg(short='t',long,default_value_t=UnnafOutput::Fasta)]
     output_type: UnnafOutput,
-    #[arg(long,value_name="N",default_value_t=80,help="Override line length to N")]
-    line_length: u16,
+    #[arg(long,value_name="N",help="Override line length to N (0 disables wrapping) -- defaults to the archive's stored line length")]
+    line_length: Option<u16>,
     #[arg(long,default_value_t=false,help="Ignore Mask")]
     no_mask: bool,
     #[arg(long,default_value_t=false,help="Set STDOUT stream to binary mode")]
@@ -1,7 +1,9 @@
-use nafcodec::{DecoderBuilder,Flag,Flags};
+use nafcodec::{DecoderBuilder,Flag,Flags,FastaWriter,FastqWriter};
 
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
 use std::path::Path;
-use bio::io::{fasta,fastq};
 use crate::UnnafArgs;
 
 pub fn decode_naf(args: &UnnafArgs) {
@@ -16,7 +18,7 @@ pub fn decode_naf(args: &UnnafArgs) {
         crate::UnnafOutput::Ids =>      Flags::from(Flag::Id),
         crate::UnnafOutput::Names =>    Flags::from(Flag::Comment),
         crate::UnnafOutput::Mask =>     Flags::from(Flag::Mask),
-        crate::UnnafOutput::FourBit | 
+        crate::UnnafOutput::FourBit |
           crate::UnnafOutput::Seq |
           crate::UnnafOutput::Sequences => Flags::from(Flag::Sequence),
         crate::UnnafOutput::Fasta => Flag::Comment | Flag::Sequence | {if args.no_mask {Flag::Mask} else {Flag::Sequence}},
@@ -24,33 +26,38 @@ pub fn decode_naf(args: &UnnafArgs) {
     };
     let filepath = Path::new(&args.filename);
     let mut decoder = DecoderBuilder::from_flags(flags).with_path(filepath).unwrap();
-    // FIXME: Write to args.output instead of using println!
+
+    let mut out: Box<dyn Write> = match &args.output {
+        Some(outfile) => Box::new(BufWriter::new(File::create(outfile.to_owned()).unwrap())),
+        None => Box::new(BufWriter::new(std::io::stdout())),
+    };
+
     match args.output_type{
         crate::UnnafOutput::Format => {
             let header = decoder.header();
-            println!("{:?} sequences in NAF format {:?}",header.sequence_type(),header.format_version());
+            writeln!(out,"{:?} sequences in NAF format {:?}",header.sequence_type(),header.format_version()).unwrap();
         },
-        crate::UnnafOutput::PartList => println!("{:?}",decoder.header().flags()),
+        crate::UnnafOutput::PartList => writeln!(out,"{:?}",decoder.header().flags()).unwrap(),
         crate::UnnafOutput::Sizes => {
             let all_flags = decoder.header().flags();
             let sizes_decoder = DecoderBuilder::from_flags(all_flags).sizes_from_path(filepath).unwrap();
             for size in sizes_decoder {
-                println!("{}",size);
+                writeln!(out,"{}",size).unwrap();
             }
         },
-        crate::UnnafOutput::Lengths => println!("{:?}",decoder.lengths()),
-        crate::UnnafOutput::TotalLength => println!("{:?}",decoder.lengths().iter().sum::<u64>()),
-        crate::UnnafOutput::Number => println!("{:?}",decoder.lengths().len()),
-        crate::UnnafOutput::Title => println!("{}",decoder.title().unwrap()),
+        crate::UnnafOutput::Lengths => writeln!(out,"{:?}",decoder.lengths()).unwrap(),
+        crate::UnnafOutput::TotalLength => writeln!(out,"{:?}",decoder.lengths().iter().sum::<u64>()).unwrap(),
+        crate::UnnafOutput::Number => writeln!(out,"{:?}",decoder.lengths().len()).unwrap(),
+        crate::UnnafOutput::Title => writeln!(out,"{}",decoder.title().unwrap()).unwrap(),
         crate::UnnafOutput::Ids => {
             for record in decoder {
-                println!("{}",record.unwrap().id.unwrap());
+                writeln!(out,"{}",record.unwrap().id.unwrap()).unwrap();
             }
         },
         crate::UnnafOutput::Names => {
             for record in decoder {
                 if let Ok(ok_rec) = record {
-                    println!("{} {}",ok_rec.id.unwrap(),ok_rec.comment.unwrap());
+                    writeln!(out,"{} {}",ok_rec.id.unwrap(),ok_rec.comment.unwrap()).unwrap();
                 }
             }
         },
@@ -59,50 +66,44 @@ pub fn decode_naf(args: &UnnafArgs) {
         crate::UnnafOutput::Seq => {
             for record in decoder {
                 if let Ok(ok_rec) = record {
-                    print!("{}",std::str::from_utf8(&ok_rec.sequence.unwrap()).unwrap());
+                    write!(out,"{}",ok_rec.sequence.unwrap()).unwrap();
                 }
             }
         },
         crate::UnnafOutput::Sequences => {
             for record in decoder {
                 if let Ok(ok_rec) = record {
-                    println!("{}",std::str::from_utf8(&ok_rec.sequence.unwrap()).unwrap());
+                    writeln!(out,"{}",ok_rec.sequence.unwrap()).unwrap();
                 }
             }
         },
         crate::UnnafOutput::Fasta => {
+            // defaults to the archive's own stored line length; `--line-length 0`
+            // disables wrapping entirely
+            let line_length = args
+                .line_length
+                .map(|n| n as usize)
+                .unwrap_or(decoder.header().line_length() as usize);
+            let mut writer = FastaWriter::new(out, line_length);
             for record in decoder {
                 if let Ok(ok_rec) = record {
-                    let seq = ok_rec.sequence.unwrap();
-                    let id = ok_rec.id.unwrap().clone();
-                    let comment = ok_rec.comment.as_deref().clone();
-                    let fasta_record = fasta::Record::with_attrs(
-                        &id,
-                        comment,
-                        &seq);
-                    // FIXME: does not wrap lines
-                    print!("{}",fasta_record);
+                    writer.write_record(&ok_rec).unwrap();
                 }
-
             }
+            writer.flush().unwrap();
         },
-        crate::UnnafOutput::Fastq => 
+        crate::UnnafOutput::Fastq => {
+            let line_length = args
+                .line_length
+                .map(|n| n as usize)
+                .unwrap_or(decoder.header().line_length() as usize);
+            let mut writer = FastqWriter::new(out, line_length);
             for record in decoder {
                 if let Ok(ok_rec) = record {
-                    let seq = ok_rec.sequence.unwrap();
-                    let id = ok_rec.id.unwrap().clone();
-                    let comment = ok_rec.comment.as_deref().clone();
-                    let qual = ok_rec.quality.expect("FASTQ output requested, but input has no qualities");
-                    let fastq_record = fastq::Record::with_attrs(
-                        &id,
-                        comment,
-                        &seq,
-                        qual.as_bytes());
-                    // FIXME: does not wrap lines
-                    print!("{}",fastq_record);
+                    writer.write_record(&ok_rec).unwrap();
                 }
-
             }
+            writer.flush().unwrap();
+        },
     }
-    
 }
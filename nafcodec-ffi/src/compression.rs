@@ -0,0 +1,75 @@
+//! Transparent input decompression, shared by every `open_*` entry point.
+//!
+//! Mirrors the niffler/`CompressionExt` pattern used by `rasusa`: opening
+//! `reads.fa.gz` should just work, with no extra flag required from the
+//! caller. [`open_decompressed`] peeks the file's leading bytes with
+//! [`nafcodec::detect_format`] and, when they match a known compression
+//! signature, wraps the file in the matching streaming decoder before
+//! handing it back. The detected [`CompressionKind`] is returned alongside
+//! the reader, so callers can tell which codec, if any, was unwrapped.
+//!
+//! ASCII-armored input (see [`nafcodec::ArmorReader`]) is unwrapped the
+//! same way: it is not a compression codec, but it is just as opaque to
+//! the `open_*` parsers, so it is sniffed and stripped at the same point.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::io::Read;
+
+use nafcodec::error::Error;
+use nafcodec::ArmorReader;
+use nafcodec::InputFormat;
+
+/// The compression codec detected on an input file, if any.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionKind {
+    /// The input was not compressed.
+    None,
+    /// The input was gzip-compressed.
+    Gzip,
+    /// The input was bzip2-compressed.
+    Bzip2,
+    /// The input was Zstandard-compressed.
+    Zstd,
+    /// The input was xz-compressed.
+    Xz,
+    /// The input was ASCII-armored.
+    Armor,
+}
+
+/// Open `filename`, transparently unwrapping any recognized compression.
+///
+/// Returns the [`CompressionKind`] that was detected, together with a
+/// reader that yields the decompressed bytes of the file (or the raw bytes
+/// unchanged, if no compression was detected). Formats that are not a
+/// compression codec (`NAF`, `FASTA`, `FASTQ`) are left untouched, since
+/// they are the actual record formats `open_*` callers want to parse.
+pub fn open_decompressed(filename: &str) -> Result<(CompressionKind, Box<dyn Read>), Error> {
+    let file = File::open(filename)?;
+    let mut reader = BufReader::new(file);
+    let format = nafcodec::detect_format(&mut reader);
+    match format {
+        Ok(InputFormat::Gzip) => Ok((
+            CompressionKind::Gzip,
+            Box::new(flate2::read::GzDecoder::new(reader)),
+        )),
+        Ok(InputFormat::Bzip2) => Ok((
+            CompressionKind::Bzip2,
+            Box::new(bzip2::read::BzDecoder::new(reader)),
+        )),
+        Ok(InputFormat::Zstd) => Ok((
+            CompressionKind::Zstd,
+            Box::new(zstd::stream::read::Decoder::new(reader)?),
+        )),
+        Ok(InputFormat::Xz) => Ok((CompressionKind::Xz, Box::new(xz2::read::XzDecoder::new(reader)))),
+        Ok(InputFormat::Armored) => Ok((
+            CompressionKind::Armor,
+            Box::new(ArmorReader::new(reader)?),
+        )),
+        // Anything else (NAF, FASTA, FASTQ, or an unrecognized format) is
+        // handed back unchanged: `open_*` parses the record format itself,
+        // and an unrecognized format is the parser's problem to reject, not
+        // this helper's.
+        _ => Ok((CompressionKind::None, Box::new(reader))),
+    }
+}
@@ -0,0 +1,59 @@
+//! Parse FASTA records into an in-memory NAF archive.
+
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+
+use nafcodec::error::Error;
+use nafcodec::Encoder;
+use nafcodec::EncoderBuilder;
+use nafcodec::Memory;
+use nafcodec::Record;
+use nafcodec::SequenceType;
+
+/// Parse `reader` as FASTA and build an in-memory NAF archive from it.
+///
+/// `>id comment` header lines start a new record; every following line up
+/// to the next header is joined into that record's (possibly line-wrapped)
+/// sequence. `buffer_size` sets the capacity of the internal line buffer.
+pub fn naf_from_fasta<R: Read>(reader: R, buffer_size: usize) -> Result<Encoder<'static, Memory>, Error> {
+    let mut builder = EncoderBuilder::new(SequenceType::Dna);
+    builder.id(true).comment(true).sequence(true);
+    let mut encoder = builder.with_memory()?;
+
+    let mut lines = BufReader::with_capacity(buffer_size, reader).lines();
+    let mut id: Option<String> = None;
+    let mut comment = String::new();
+    let mut sequence = String::new();
+
+    macro_rules! flush_record {
+        () => {
+            if let Some(id) = id.take() {
+                let record = Record {
+                    id: Some(id.into()),
+                    comment: Some(std::mem::take(&mut comment).into()),
+                    length: Some(sequence.len() as u64),
+                    sequence: Some(std::mem::take(&mut sequence).into()),
+                    quality: None,
+                    mask: None,
+                };
+                encoder.push(&record)?;
+            }
+        };
+    }
+
+    for line in &mut lines {
+        let line = line?;
+        if let Some(header) = line.strip_prefix('>') {
+            flush_record!();
+            let mut parts = header.splitn(2, char::is_whitespace);
+            id = parts.next().map(str::to_string);
+            comment = parts.next().unwrap_or("").to_string();
+        } else {
+            sequence.push_str(line.trim_end());
+        }
+    }
+    flush_record!();
+
+    Ok(encoder)
+}
@@ -0,0 +1,71 @@
+//! Parse FASTQ records into an in-memory NAF archive.
+
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+
+use nafcodec::error::Error;
+use nafcodec::Encoder;
+use nafcodec::EncoderBuilder;
+use nafcodec::Memory;
+use nafcodec::Record;
+use nafcodec::SequenceType;
+
+fn truncated_record() -> Error {
+    Error::Io(std::io::Error::new(
+        std::io::ErrorKind::UnexpectedEof,
+        "truncated FASTQ record",
+    ))
+}
+
+/// Parse `reader` as FASTQ and build an in-memory NAF archive from it.
+///
+/// Analogous to [`crate::fasta::naf_from_fasta`], but each four-line record
+/// also stores its quality string into the archive's quality block. Both
+/// the sequence and quality lines may themselves be wrapped across several
+/// lines, as is common for long reads; the quality lines for a record are
+/// read until they add up to the same length as its sequence, mirroring
+/// how most FASTQ readers tell the quality block apart from the next
+/// record's `@id` line. [`Encoder::push`] already rejects a sequence/quality
+/// length mismatch with [`Error::InvalidLength`].
+pub fn naf_from_fastq<R: Read>(reader: R, buffer_size: usize) -> Result<Encoder<'static, Memory>, Error> {
+    let mut builder = EncoderBuilder::new(SequenceType::Dna);
+    builder.id(true).comment(true).sequence(true).quality(true);
+    let mut encoder = builder.with_memory()?;
+
+    let mut lines = BufReader::with_capacity(buffer_size, reader).lines();
+    while let Some(header) = lines.next() {
+        let header = header?;
+        let header = header.strip_prefix('@').ok_or(Error::InvalidSequence)?;
+        let mut parts = header.splitn(2, char::is_whitespace);
+        let id = parts.next().unwrap_or("").to_string();
+        let comment = parts.next().unwrap_or("").to_string();
+
+        let mut sequence = String::new();
+        loop {
+            let line = lines.next().ok_or_else(truncated_record)??;
+            if line.starts_with('+') {
+                break;
+            }
+            sequence.push_str(line.trim_end());
+        }
+
+        let mut quality = String::new();
+        while quality.len() < sequence.len() {
+            let line = lines.next().ok_or_else(truncated_record)??;
+            quality.push_str(line.trim_end());
+        }
+
+        let record = Record {
+            id: Some(id.into()),
+            comment: Some(comment.into()),
+            length: Some(sequence.len() as u64),
+            sequence: Some(sequence.into()),
+            quality: Some(quality.into()),
+            mask: None,
+        };
+        encoder.push(&record)?;
+    }
+
+    Ok(encoder)
+}
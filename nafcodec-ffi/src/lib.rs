@@ -8,6 +8,7 @@ use nafcodec::Flag;
 use nafcodec::Flags;
 use nafcodec::FormatVersion;
 use nafcodec::Header;
+use nafcodec::InputFormat;
 use nafcodec::Record;
 use nafcodec::SequenceType;
 use nafcodec::Decoder;
@@ -16,10 +17,16 @@ use nafcodec::Encoder;
 use nafcodec::EncoderBuilder;
 use nafcodec::Memory;
 use nafcodec::Storage;
+use nafcodec::StreamDecoder;
+mod compression;
 mod ennaf;
+mod fasta;
+mod fastq;
 mod unnaf;
 use safer_ffi::*;
 
+use std::fs::File;
+use std::io::BufReader;
 use std::os::raw::c_char;
 
 #[no_mangle]
@@ -27,6 +34,75 @@ pub extern "C" fn print_title(filename: String) -> String {
     todo!();
 }
 
+/// Heuristically detect the format of the file at `filename`.
+///
+/// Returns the format name (`"NAF"`, `"FASTA"`, `"FASTQ"`, `"gzip"`,
+/// `"bzip2"`, `"zstd"`, `"xz"` or `"armor"`), or `"unknown"` if the file
+/// could not be opened or its format could not be recognized from its
+/// leading bytes.
+#[no_mangle]
+pub extern "C" fn detect_input_format(filename: String) -> String {
+    let detected = File::open(&filename)
+        .map_err(nafcodec::error::Error::from)
+        .and_then(|file| nafcodec::detect_format(&mut BufReader::new(file)));
+    match detected {
+        Ok(InputFormat::Naf) => "NAF".to_string(),
+        Ok(InputFormat::Fasta) => "FASTA".to_string(),
+        Ok(InputFormat::Fastq) => "FASTQ".to_string(),
+        Ok(InputFormat::Gzip) => "gzip".to_string(),
+        Ok(InputFormat::Bzip2) => "bzip2".to_string(),
+        Ok(InputFormat::Zstd) => "zstd".to_string(),
+        Ok(InputFormat::Xz) => "xz".to_string(),
+        Ok(InputFormat::Armored) => "armor".to_string(),
+        Err(_) => "unknown".to_string(),
+    }
+}
+
+/// Try to open a NAF archive from a file name, transparently decompressing
+/// it first if it is gzip/bzip2/zstd/xz-compressed.
+///
+/// Returns whether `filename` could be opened and decoded as a NAF archive.
+#[no_mangle]
+pub extern "C" fn open_NAF(filename: String) -> bool {
+    match compression::open_decompressed(&filename) {
+        Ok((_kind, reader)) => StreamDecoder::new(std::io::BufReader::new(reader)).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Convert a (possibly compressed) FASTA file into a NAF archive.
+///
+/// Returns whether `filename` was successfully parsed as FASTA and the
+/// resulting archive written to `output_filename`.
+#[no_mangle]
+pub extern "C" fn open_FASTA(filename: String, output_filename: String) -> bool {
+    convert(&filename, &output_filename, fasta::naf_from_fasta)
+}
+
+/// Convert a (possibly compressed) FASTQ file into a NAF archive.
+///
+/// Returns whether `filename` was successfully parsed as FASTQ and the
+/// resulting archive, including its quality block, written to
+/// `output_filename`.
+#[no_mangle]
+pub extern "C" fn open_FASTQ(filename: String, output_filename: String) -> bool {
+    convert(&filename, &output_filename, fastq::naf_from_fastq)
+}
+
+/// Shared plumbing for `open_FASTA`/`open_FASTQ`: decompress, parse, write.
+fn convert<F>(filename: &str, output_filename: &str, naf_from: F) -> bool
+where
+    F: FnOnce(Box<dyn std::io::Read>, usize) -> Result<Encoder<'static, Memory>, nafcodec::error::Error>,
+{
+    let result = compression::open_decompressed(filename)
+        .and_then(|(_kind, reader)| naf_from(reader, 1024))
+        .and_then(|encoder| {
+            let output = File::create(output_filename).map_err(nafcodec::error::Error::from)?;
+            encoder.write(output)
+        });
+    result.is_ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
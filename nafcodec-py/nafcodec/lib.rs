@@ -4,31 +4,39 @@
 extern crate pyo3_built;
 extern crate nafcodec;
 extern crate pyo3;
+extern crate tempfile;
 
 mod pyfile;
 
 use std::borrow::Cow;
 use std::convert::Infallible;
 use std::io::BufReader;
+use std::io::Write;
 use std::ops::DerefMut;
 
 use nafcodec::DecoderBuilder;
+use nafcodec::Flag;
 use pyo3::exceptions::PyFileNotFoundError;
+use pyo3::exceptions::PyIndexError;
 use pyo3::exceptions::PyIsADirectoryError;
 use pyo3::exceptions::PyOSError;
 use pyo3::exceptions::PyRuntimeError;
 use pyo3::exceptions::PyUnicodeError;
 use pyo3::exceptions::PyValueError;
+use pyo3::buffer::PyBuffer;
 use pyo3::prelude::*;
+use pyo3::types::PyBytes;
 use pyo3::types::PyDict;
 use pyo3::types::PyList;
 use pyo3::types::PyString;
 use pyo3::PyTypeInfo;
 
+use self::pyfile::PyFileBuffer;
 use self::pyfile::PyFileRead;
 use self::pyfile::PyFileReadWrapper;
 use self::pyfile::PyFileWrite;
 use self::pyfile::PyFileWriteWrapper;
+use self::pyfile::PyIoError;
 
 #[allow(dead_code)]
 mod build {
@@ -51,25 +59,36 @@ fn convert_error(_py: Python, error: nafcodec::error::Error, path: Option<&str>)
         Error::InvalidSequence => PyValueError::new_err("invalid characters found in sequence"),
         Error::Io(io_error) => {
             let desc = io_error.to_string();
-            if let Some(p) = path.map(str::to_string) {
-                match io_error.raw_os_error() {
-                    Some(2) => PyFileNotFoundError::new_err((p,)),
-                    #[cfg(target_os = "windows")]
-                    Some(3) => PyFileNotFoundError::new_err((p,)),
-                    #[cfg(not(target_os = "windows"))]
-                    Some(21) => PyIsADirectoryError::new_err((p,)),
-                    Some(code) => PyOSError::new_err((code, desc, p)),
-                    None => PyOSError::new_err((desc,)),
-                }
-            } else {
-                match io_error.raw_os_error() {
-                    Some(2) => PyFileNotFoundError::new_err((desc,)),
-                    #[cfg(target_os = "windows")]
-                    Some(3) => PyFileNotFoundError::new_err((desc,)),
-                    #[cfg(not(target_os = "windows"))]
-                    Some(21) => PyIsADirectoryError::new_err((desc,)),
-                    Some(code) => PyOSError::new_err((code, desc)),
-                    None => PyOSError::new_err((desc,)),
+            let raw_os_error = io_error.raw_os_error();
+            // If this error originated from a Python file object (see
+            // `transmute_file_error`/`PyIoError`), re-raise the exact same
+            // exception instead of flattening it into a generic `OSError`,
+            // so its type, message and `__cause__` chain survive the round
+            // trip through the Rust codec.
+            match io_error.into_inner().and_then(|e| e.downcast::<PyIoError>().ok()) {
+                Some(py_io_error) => py_io_error.0,
+                None => {
+                    if let Some(p) = path.map(str::to_string) {
+                        match raw_os_error {
+                            Some(2) => PyFileNotFoundError::new_err((p,)),
+                            #[cfg(target_os = "windows")]
+                            Some(3) => PyFileNotFoundError::new_err((p,)),
+                            #[cfg(not(target_os = "windows"))]
+                            Some(21) => PyIsADirectoryError::new_err((p,)),
+                            Some(code) => PyOSError::new_err((code, desc, p)),
+                            None => PyOSError::new_err((desc,)),
+                        }
+                    } else {
+                        match raw_os_error {
+                            Some(2) => PyFileNotFoundError::new_err((desc,)),
+                            #[cfg(target_os = "windows")]
+                            Some(3) => PyFileNotFoundError::new_err((desc,)),
+                            #[cfg(not(target_os = "windows"))]
+                            Some(21) => PyIsADirectoryError::new_err((desc,)),
+                            Some(code) => PyOSError::new_err((code, desc)),
+                            None => PyOSError::new_err((desc,)),
+                        }
+                    }
                 }
             }
         }
@@ -154,6 +173,20 @@ impl<'py> FromPyObject<'py> for OpenMode {
 
 // ---------------------------------------------------------------------------
 
+/// Convert a `str` or buffer-protocol object (`bytes`, `bytearray`,
+/// `memoryview`, NumPy arrays, ...) into a `PyString`, without requiring
+/// the caller to go through `str` first.
+fn to_pystring<'py>(py: Python<'py>, obj: &Bound<'py, PyAny>) -> PyResult<Py<PyString>> {
+    if let Ok(s) = obj.downcast::<PyString>() {
+        return Ok(s.clone().unbind());
+    }
+    let buffer = obj.extract::<PyBuffer<u8>>()?;
+    let bytes = buffer.to_vec(py)?;
+    let s = std::str::from_utf8(&bytes)
+        .map_err(|_| PyUnicodeError::new_err("failed to decode UTF-8 data"))?;
+    Ok(PyString::new(py, s).unbind())
+}
+
 /// A single sequence record stored in a Nucleotide Archive Format file.
 #[pyclass(module = "nafcodec")]
 #[derive(Clone, Debug)]
@@ -170,6 +203,18 @@ pub struct Record {
     /// `str` or `None`: The record quality.
     #[pyo3(get, set)]
     quality: Option<Py<PyString>>,
+    /// `bytes` or `None`: The record sequence, as raw bytes.
+    ///
+    /// Only populated when the record was obtained from a `Decoder`
+    /// opened with `raw=True`; `sequence` is then left as `None`, since
+    /// building it would require a redundant UTF-8 validation pass.
+    #[pyo3(get)]
+    sequence_bytes: Option<Py<PyBytes>>,
+    /// `bytes` or `None`: The record quality, as raw bytes.
+    ///
+    /// See `sequence_bytes` for when this is populated instead of `quality`.
+    #[pyo3(get)]
+    quality_bytes: Option<Py<PyBytes>>,
     /// `str` or `None`: The record sequence length.
     #[pyo3(get, set)]
     length: Option<u64>,
@@ -187,6 +232,33 @@ impl Record {
             sequence,
             comment,
             quality,
+            sequence_bytes: None,
+            quality_bytes: None,
+            length,
+        }
+    }
+
+    /// Build a record the same way as [`Record::from_py`], but leaving
+    /// `sequence`/`quality` as raw `bytes` instead of decoding them into a
+    /// `str`, skipping the UTF-8 validation and transcoding that
+    /// `PyString::new` would otherwise perform on every record.
+    pub fn from_py_raw<'py>(py: Python<'py>, record: nafcodec::Record) -> Self {
+        let id = record.id.map(|x| PyString::new(py, &x).into());
+        let comment = record.comment.map(|x| PyString::new(py, &x).into());
+        let sequence_bytes = record
+            .sequence
+            .map(|x| PyBytes::new(py, x.as_bytes()).into());
+        let quality_bytes = record
+            .quality
+            .map(|x| PyBytes::new(py, x.as_bytes()).into());
+        let length = record.length;
+        Self {
+            id,
+            comment,
+            sequence: None,
+            quality: None,
+            sequence_bytes,
+            quality_bytes,
             length,
         }
     }
@@ -200,10 +272,12 @@ impl Record {
         py: Python<'py>,
         id: Option<Py<PyString>>,
         comment: Option<Py<PyString>>,
-        sequence: Option<Py<PyString>>,
-        quality: Option<Py<PyString>>,
+        sequence: Option<Bound<'py, PyAny>>,
+        quality: Option<Bound<'py, PyAny>>,
         mut length: Option<u64>,
     ) -> PyResult<PyClassInitializer<Self>> {
+        let sequence = sequence.as_ref().map(|s| to_pystring(py, s)).transpose()?;
+        let quality = quality.as_ref().map(|q| to_pystring(py, q)).transpose()?;
         // Check lengths are consistent.
         if let Some(seq) = sequence.as_ref() {
             if let Some(qual) = quality.as_ref() {
@@ -240,6 +314,8 @@ impl Record {
             comment,
             sequence,
             quality,
+            sequence_bytes: None,
+            quality_bytes: None,
             length,
         }))
     }
@@ -292,27 +368,32 @@ impl TryFrom<&Record> for nafcodec::Record<'static> {
                 .transpose()?
                 .map(String::from)
                 .map(Cow::Owned);
-            let sequence = value
-                .sequence
-                .as_ref()
-                .map(|s| s.to_str(py))
-                .transpose()?
-                .map(String::from)
-                .map(Cow::Owned);
-            let quality = value
-                .quality
-                .as_ref()
-                .map(|s| s.to_str(py))
-                .transpose()?
-                .map(String::from)
-                .map(Cow::Owned);
-            let length = value.length.clone();
+            let sequence = match (&value.sequence, &value.sequence_bytes) {
+                (Some(s), _) => Some(Cow::Owned(String::from(s.to_str(py)?))),
+                (None, Some(b)) => Some(Cow::Owned(
+                    std::str::from_utf8(b.as_bytes(py))
+                        .map_err(|_| PyUnicodeError::new_err("failed to decode UTF-8 data"))?
+                        .to_owned(),
+                )),
+                (None, None) => None,
+            };
+            let quality = match (&value.quality, &value.quality_bytes) {
+                (Some(s), _) => Some(Cow::Owned(String::from(s.to_str(py)?))),
+                (None, Some(b)) => Some(Cow::Owned(
+                    std::str::from_utf8(b.as_bytes(py))
+                        .map_err(|_| PyUnicodeError::new_err("failed to decode UTF-8 data"))?
+                        .to_owned(),
+                )),
+                (None, None) => None,
+            };
+            let length = value.length;
             Ok(nafcodec::Record {
                 id,
                 comment,
                 sequence,
                 quality,
                 length,
+                mask: None,
             })
         })
     }
@@ -320,16 +401,101 @@ impl TryFrom<&Record> for nafcodec::Record<'static> {
 
 // ---------------------------------------------------------------------------
 
+/// The decoder backend picked by `Decoder.__init__` depending on `threads`.
+///
+/// With `threads <= 1`, a regular seekable [`nafcodec::Decoder`] is used,
+/// which supports indexing through `Decoder.record`. With `threads > 1` and
+/// an archive opened from a path, the id/comment/length/mask/sequence/quality
+/// blocks are instead decompressed up front, in parallel, on independent
+/// file handles (see [`nafcodec::DecoderBuilder::with_path_threaded`]); this
+/// yields a forward-only [`nafcodec::StreamDecoder`] instead.
+enum DecoderKind {
+    Seekable(nafcodec::Decoder<'static, BufReader<PyFileReadWrapper>>),
+    Threaded(nafcodec::StreamDecoder),
+}
+
+impl DecoderKind {
+    fn header(&self) -> &nafcodec::Header {
+        match self {
+            DecoderKind::Seekable(d) => d.header(),
+            DecoderKind::Threaded(d) => d.header(),
+        }
+    }
+
+    fn sequence_type(&self) -> nafcodec::SequenceType {
+        match self {
+            DecoderKind::Seekable(d) => d.sequence_type(),
+            DecoderKind::Threaded(d) => d.sequence_type(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            DecoderKind::Seekable(d) => d.len(),
+            DecoderKind::Threaded(d) => d.len(),
+        }
+    }
+
+    fn position(&self) -> u64 {
+        match self {
+            DecoderKind::Seekable(d) => d.position(),
+            DecoderKind::Threaded(d) => d.position(),
+        }
+    }
+
+    fn next(&mut self) -> Option<Result<nafcodec::Record<'static>, nafcodec::error::Error>> {
+        match self {
+            DecoderKind::Seekable(d) => d.next(),
+            DecoderKind::Threaded(d) => d.next(),
+        }
+    }
+
+    /// Fetch the `index`-th record, skipping over the ones before it.
+    fn record(&mut self, index: u64) -> Result<nafcodec::Record<'static>, nafcodec::error::Error> {
+        use nafcodec::error::Error;
+        match self {
+            DecoderKind::Seekable(d) => d.record(index),
+            DecoderKind::Threaded(d) => {
+                if index < d.position() {
+                    return Err(Error::Io(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "cannot seek backwards to an already-consumed record",
+                    )));
+                }
+                while d.position() < index {
+                    d.next().transpose()?;
+                }
+                d.next().transpose()?.ok_or_else(|| {
+                    Error::Io(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "record index out of bounds",
+                    ))
+                })
+            }
+        }
+    }
+}
+
 /// A streaming decoder to read a Nucleotide Archive Format file.
 #[pyclass(module = "nafcodec")]
 pub struct Decoder {
-    decoder: nafcodec::Decoder<'static, BufReader<PyFileReadWrapper>>,
+    decoder: DecoderKind,
+    builder: DecoderBuilder,
+    /// The path the archive was opened from, if any, used to support
+    /// `__getitem__` with an index before the decoder's current position:
+    /// the underlying content blocks are plain Zstandard streams that can
+    /// only be read forward, so rewinding re-opens a fresh decoder.
+    path: Option<String>,
+    /// Whether to decode `sequence`/`quality` into `bytes` instead of `str`.
+    raw: bool,
+    /// The number of worker threads used to decompress content blocks.
+    threads: usize,
 }
 
 #[pymethods]
 impl Decoder {
     #[new]
-    #[pyo3(signature = (file, *, id=true, comment=true, sequence=true, quality=true, mask=true, buffer_size=None))]
+    #[pyo3(signature = (file, *, id=true, comment=true, sequence=true, quality=true, mask=true, buffer_size=None, raw=false, threads=1))]
     pub fn __init__<'py>(
         file: Bound<'py, PyAny>,
         id: bool,
@@ -338,45 +504,89 @@ impl Decoder {
         quality: bool,
         mask: bool,
         buffer_size: Option<usize>,
+        raw: bool,
+        threads: usize,
     ) -> PyResult<PyClassInitializer<Self>> {
         let py = file.py();
 
+        let buffer_size = buffer_size.map(Ok).unwrap_or_else(|| {
+            py.import(pyo3::intern!(py, "io"))?
+                .getattr(pyo3::intern!(py, "DEFAULT_BUFFER_SIZE"))?
+                .extract::<usize>()
+        })?;
+
         let mut builder = DecoderBuilder::new();
         builder.id(id);
         builder.comment(comment);
         builder.sequence(sequence);
         builder.quality(quality);
         builder.mask(mask);
-        builder.buffer_size(buffer_size.map(Ok).unwrap_or_else(|| {
-            py.import(pyo3::intern!(py, "io"))?
-                .getattr(pyo3::intern!(py, "DEFAULT_BUFFER_SIZE"))?
-                .extract::<usize>()
-        })?);
+        builder.buffer_size(buffer_size);
 
-        let decoder = match PyFileRead::from_ref(&file) {
-            Ok(handle) => {
-                let wrapper = PyFileReadWrapper::PyFile(handle);
-                builder
-                    .with_reader(std::io::BufReader::new(wrapper))
-                    .map_err(|e| convert_error(py, e, None))?
-            }
-            Err(_e) => {
-                let path = py
-                    .import("os")?
-                    .call_method1(pyo3::intern!(py, "fspath"), (file,))?
-                    .extract::<Bound<'_, PyString>>()?;
-                let path_str = path.to_str()?;
-                let wrapper = std::fs::File::open(path_str)
-                    .map_err(nafcodec::error::Error::Io)
-                    .map_err(|e| convert_error(py, e, Some(path_str)))
-                    .map(PyFileReadWrapper::File)?;
-                builder
-                    .with_reader(std::io::BufReader::new(wrapper))
-                    .map_err(|e| convert_error(py, e, Some(path_str)))?
+        let mut path = None;
+        // Probe for the buffer protocol first (e.g. `bytes`, `memoryview`,
+        // or `mmap.mmap`): it lets the whole archive be borrowed once and
+        // read back without ever calling into Python again, which beats
+        // both of the other paths below. Only objects that do not support
+        // it fall through to the file-like `read`/`readinto` detection.
+        let decoder = if let Ok(buffer) = PyFileBuffer::from_ref(&file) {
+            let wrapper = PyFileReadWrapper::Buffer(buffer);
+            let decoder = builder
+                .with_reader(std::io::BufReader::with_capacity(buffer_size, wrapper))
+                .map_err(|e| convert_error(py, e, None))?;
+            DecoderKind::Seekable(decoder)
+        } else {
+            match PyFileRead::from_ref(&file) {
+                Ok(handle) => {
+                    let wrapper = PyFileReadWrapper::PyFile(handle);
+                    // Buffer reads at the same size as the internal per-block
+                    // buffers (see `DecoderBuilder::buffer_size`), so that
+                    // `PyFileRead`'s GIL-acquiring `read`/`readinto` calls are
+                    // made in large, infrequent chunks instead of once per
+                    // small internal read. `BufReader<R: Seek>` already adjusts
+                    // the reported position for unconsumed buffered bytes and
+                    // discards the buffer before seeking the inner reader, so
+                    // seeking through the wrapper stays correct.
+                    let decoder = builder
+                        .with_reader(std::io::BufReader::with_capacity(buffer_size, wrapper))
+                        .map_err(|e| convert_error(py, e, None))?;
+                    DecoderKind::Seekable(decoder)
+                }
+                Err(_e) => {
+                    let path_obj = py
+                        .import("os")?
+                        .call_method1(pyo3::intern!(py, "fspath"), (file,))?
+                        .extract::<Bound<'_, PyString>>()?;
+                    let path_str = path_obj.to_str()?;
+                    let decoder = if threads > 1 {
+                        builder
+                            .with_path_threaded(path_str, threads)
+                            .map_err(|e| convert_error(py, e, Some(path_str)))
+                            .map(DecoderKind::Threaded)?
+                    } else {
+                        let wrapper = std::fs::File::open(path_str)
+                            .map_err(nafcodec::error::Error::Io)
+                            .map_err(|e| convert_error(py, e, Some(path_str)))
+                            .map(PyFileReadWrapper::File)?;
+                        builder
+                            .with_reader(std::io::BufReader::new(wrapper))
+                            .map_err(|e| convert_error(py, e, Some(path_str)))
+                            .map(DecoderKind::Seekable)?
+                    };
+                    path = Some(path_str.to_string());
+                    decoder
+                }
             }
         };
 
-        Ok(Decoder { decoder }.into())
+        Ok(Decoder {
+            decoder,
+            builder,
+            path,
+            raw,
+            threads,
+        }
+        .into())
     }
 
     pub fn __iter__(slf: PyRef<'_, Self>) -> PyResult<PyRef<'_, Self>> {
@@ -389,10 +599,15 @@ impl Decoder {
 
     pub fn __next__(mut slf: PyRefMut<'_, Self>) -> PyResult<Option<Record>> {
         let py = slf.py();
+        let raw = slf.raw;
         let result = slf.deref_mut().decoder.next().transpose();
         match result {
             Ok(None) => Ok(None),
-            Ok(Some(record)) => Ok(Some(Record::from_py(py, record))),
+            Ok(Some(record)) => Ok(Some(if raw {
+                Record::from_py_raw(py, record)
+            } else {
+                Record::from_py(py, record)
+            })),
             Err(e) => Err(convert_error(py, e, None)),
         }
     }
@@ -451,22 +666,125 @@ impl Decoder {
     /// This method will returns `None` when no more records are available.
     pub fn read(mut slf: PyRefMut<'_, Self>) -> PyResult<Option<Record>> {
         let py = slf.py();
+        let raw = slf.raw;
         let result = slf.deref_mut().decoder.next().transpose();
         match result {
             Ok(None) => Ok(None),
-            Ok(Some(record)) => Ok(Some(Record::from_py(py, record))),
+            Ok(Some(record)) => Ok(Some(if raw {
+                Record::from_py_raw(py, record)
+            } else {
+                Record::from_py(py, record)
+            })),
             Err(e) => Err(convert_error(py, e, None)),
         }
     }
+
+    /// Get the record at the given index without consuming the whole archive.
+    ///
+    /// Supports negative indices the same way Python sequences do. If
+    /// `index` is before the decoder's current position, the archive is
+    /// transparently re-opened from the path it was obtained from; this
+    /// only works for archives opened from a path or `os.PathLike`, since
+    /// a file-like object cannot always be rewound and re-read.
+    pub fn __getitem__(mut slf: PyRefMut<'_, Self>, index: isize) -> PyResult<Record> {
+        let py = slf.py();
+        let n = slf.decoder.header().number_of_sequences() as isize;
+        let index = if index < 0 { index + n } else { index };
+        if index < 0 || index >= n {
+            return Err(PyIndexError::new_err("record index out of range"));
+        }
+        let index = index as u64;
+        if index < slf.decoder.position() {
+            slf.reopen(py)?;
+        }
+        let raw = slf.raw;
+        slf.deref_mut()
+            .decoder
+            .record(index)
+            .map(|record| {
+                if raw {
+                    Record::from_py_raw(py, record)
+                } else {
+                    Record::from_py(py, record)
+                }
+            })
+            .map_err(|e| convert_error(py, e, slf.path.as_deref()))
+    }
+
+    /// Read the record at the given index.
+    ///
+    /// This method is a shortcut for `decoder[index]`, see
+    /// `Decoder.__getitem__`.
+    pub fn read_record(slf: PyRefMut<'_, Self>, index: isize) -> PyResult<Record> {
+        Self::__getitem__(slf, index)
+    }
+}
+
+impl Decoder {
+    /// Re-open the archive from its source path, discarding any progress.
+    fn reopen(&mut self, py: Python) -> PyResult<()> {
+        let path_str = self.path.as_deref().ok_or_else(|| {
+            PyValueError::new_err(
+                "cannot seek backwards: archive was not opened from a path",
+            )
+        })?;
+        self.decoder = if self.threads > 1 {
+            self.builder
+                .with_path_threaded(path_str, self.threads)
+                .map_err(|e| convert_error(py, e, Some(path_str)))
+                .map(DecoderKind::Threaded)?
+        } else {
+            let wrapper = std::fs::File::open(path_str)
+                .map_err(nafcodec::error::Error::Io)
+                .map_err(|e| convert_error(py, e, Some(path_str)))
+                .map(PyFileReadWrapper::File)?;
+            self.builder
+                .with_reader(std::io::BufReader::new(wrapper))
+                .map_err(|e| convert_error(py, e, Some(path_str)))
+                .map(DecoderKind::Seekable)?
+        };
+        Ok(())
+    }
 }
 
 // ---------------------------------------------------------------------------
 
+/// The storage backend picked by `Encoder.__init__` depending on `storage`.
+///
+/// `"memory"` (the default) keeps every block buffered in RAM until
+/// `close()`, same as before. `"tempfile"` instead spills each block to its
+/// own temporary file (optionally under `temp_dir`), concatenated into the
+/// final archive at `close()`, trading memory for disk space when encoding
+/// archives too large to hold resident.
+enum EncoderKind {
+    Memory(nafcodec::Encoder<'static, nafcodec::Memory>),
+    Tempfile(nafcodec::Encoder<'static, tempfile::TempDir>),
+}
+
+impl EncoderKind {
+    fn push(&mut self, record: &nafcodec::Record) -> Result<(), nafcodec::error::Error> {
+        match self {
+            EncoderKind::Memory(e) => e.push(record),
+            EncoderKind::Tempfile(e) => e.push(record),
+        }
+    }
+
+    fn write<W: Write>(self, file: W) -> Result<(), nafcodec::error::Error> {
+        match self {
+            EncoderKind::Memory(e) => e.write(file),
+            EncoderKind::Tempfile(e) => e.write(file),
+        }
+    }
+}
+
 /// An encoder to iteratively write a Nucleotide Archive Format file.
 #[pyclass(module = "nafcodec")]
 pub struct Encoder {
-    encoder: Option<nafcodec::Encoder<'static, nafcodec::Memory>>,
-    file: PyFileWriteWrapper,
+    encoder: Option<EncoderKind>,
+    /// Buffered so that [`Encoder::close`]'s single `write` call, which
+    /// issues many small internal writes, does not make a GIL-acquiring
+    /// Python call for each of them.
+    file: std::io::BufWriter<PyFileWriteWrapper>,
 }
 
 #[pymethods]
@@ -481,6 +799,8 @@ impl Encoder {
         sequence = false,
         quality = false,
         compression_level = 0,
+        storage = "memory",
+        temp_dir = None,
     ))]
     pub fn __init__<'py>(
         file: Bound<'py, PyAny>,
@@ -490,6 +810,8 @@ impl Encoder {
         sequence: bool,
         quality: bool,
         compression_level: i32,
+        storage: &str,
+        temp_dir: Option<Bound<'py, PyAny>>,
     ) -> PyResult<PyClassInitializer<Self>> {
         let py = file.py();
         let file = match PyFileWrite::from_ref(&file) {
@@ -506,16 +828,48 @@ impl Encoder {
                     .map(PyFileWriteWrapper::File)?
             }
         };
-        let encoder = nafcodec::EncoderBuilder::new(sequence_type.0)
+        let mut builder = nafcodec::EncoderBuilder::new(sequence_type.0);
+        builder
             .id(id)
             .comment(comment)
             .quality(quality)
             .sequence(sequence)
-            .compression_level(compression_level)
-            .with_memory()
-            .map(Some)
-            .map_err(|e| convert_error(py, e, None))?;
-        Ok(Self { file, encoder }.into())
+            .compression_level(compression_level);
+        let encoder = match storage {
+            "memory" => builder
+                .with_memory()
+                .map(EncoderKind::Memory)
+                .map_err(|e| convert_error(py, e, None))?,
+            "tempfile" => {
+                let tempdir = match temp_dir {
+                    Some(dir) => {
+                        let path = py
+                            .import("os")?
+                            .call_method1(pyo3::intern!(py, "fspath"), (dir,))?
+                            .extract::<Bound<'_, PyString>>()?;
+                        tempfile::TempDir::new_in(path.to_str()?)
+                    }
+                    None => tempfile::TempDir::new(),
+                }
+                .map_err(nafcodec::error::Error::Io)
+                .map_err(|e| convert_error(py, e, None))?;
+                builder
+                    .with_storage(tempdir)
+                    .map(EncoderKind::Tempfile)
+                    .map_err(|e| convert_error(py, e, None))?
+            }
+            other => {
+                return Err(PyValueError::new_err(format!(
+                    "invalid storage backend: {:?}",
+                    other
+                )))
+            }
+        };
+        Ok(Self {
+            file: std::io::BufWriter::new(file),
+            encoder: Some(encoder),
+        }
+        .into())
     }
 
     pub fn __enter__<'py>(slf: PyRef<'py, Self>) -> PyRef<'py, Self> {
@@ -567,17 +921,42 @@ impl Encoder {
             };
         }
 
+        // Like `borrow_field`, but for `sequence`/`quality`, which may also
+        // have been decoded as raw bytes (`Decoder(raw=True)`): in that
+        // case the bytes are borrowed directly as a `&str` instead of
+        // going through an intermediate `PyString`, since `Encoder::push`
+        // only ever reads the bytes, and the `Py<PyBytes>` outlives the call.
+        macro_rules! borrow_field_raw {
+            ($field:ident, $bytes_field:ident) => {
+                #[allow(unused_assignments)]
+                let mut borrowed = None;
+                let mut $field = None;
+                if let Some(x) = record.$field.as_ref() {
+                    let s = x.bind(py);
+                    let b = s.as_borrowed();
+                    borrowed = Some(b);
+                    $field = borrowed.as_ref().map(|b| b.to_cow()).transpose()?;
+                } else if let Some(x) = record.$bytes_field.as_ref() {
+                    let bytes = x.bind(py).as_bytes();
+                    let s = std::str::from_utf8(bytes)
+                        .map_err(|_| PyUnicodeError::new_err("failed to decode UTF-8 data"))?;
+                    $field = Some(Cow::Borrowed(s));
+                }
+            };
+        }
+
         if let Some(encoder) = slf.encoder.as_mut() {
             borrow_field!(id);
             borrow_field!(comment);
-            borrow_field!(sequence);
-            borrow_field!(quality);
+            borrow_field_raw!(sequence, sequence_bytes);
+            borrow_field_raw!(quality, quality_bytes);
             let r = nafcodec::Record {
                 id,
                 comment,
                 sequence,
                 quality,
-                length: record.length.clone(),
+                length: record.length,
+                mask: None,
             };
             encoder.push(&r).map_err(|err| convert_error(py, err, None))
         } else {
@@ -652,5 +1031,112 @@ pub fn init<'py>(py: Python<'py>, m: &Bound<'py, PyModule>) -> PyResult<()> {
         }
     }
 
+    /// Copy a subset of the records of an archive into another archive.
+    ///
+    /// Unlike reading records with a `~nafcodec.Decoder` and writing them
+    /// back out with a `~nafcodec.Encoder`, this streams records at the
+    /// Rust level: fields are never materialized as Python `str` unless
+    /// `predicate` is given, in which case only the identifier is.
+    ///
+    /// Arguments:
+    ///     src (`str`, `pathlib.Path` or file-like object): The archive
+    ///         to copy records from.
+    ///     dst (`str`, `pathlib.Path` or file-like object): The archive
+    ///         to copy the selected records into.
+    ///     ids (`set` of `str`, optional): If given, only keep records
+    ///         whose identifier is in this set.
+    ///     predicate (callable, optional): If given, called with each
+    ///         record identifier and only keeps the record if it returns
+    ///         true.
+    ///     options (`object`): Additional options to pass to the
+    ///         `~nafcodec.Decoder` and `~nafcodec.Encoder` constructors.
+    ///         Unless overriden, every field found in `src` is copied to
+    ///         `dst`.
+    ///
+    /// Example:
+    ///     Extract a handful of accessions from an archive::
+    ///
+    ///     >>> filter("LuxC.naf", "subset.naf", ids={"contig_1", "contig_2"})
+    ///
+    ///     Copy an archive without its quality scores::
+    ///
+    ///     >>> filter("reads.naf", "reads.noqual.naf", quality=False)
+    ///
+    #[pyfn(m)]
+    #[pyo3(signature = (src, dst, *, ids=None, predicate=None, **options))]
+    fn filter<'py>(
+        src: Bound<'py, PyAny>,
+        dst: Bound<'py, PyAny>,
+        ids: Option<Bound<'py, PyAny>>,
+        predicate: Option<Bound<'py, PyAny>>,
+        options: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<()> {
+        let py = src.py();
+
+        let ids = ids
+            .map(|ids| {
+                ids.try_iter()?
+                    .map(|item| item?.extract::<String>())
+                    .collect::<PyResult<std::collections::HashSet<String>>>()
+            })
+            .transpose()?;
+
+        let decoder = Decoder::type_object(py)
+            .call((src,), options)?
+            .downcast_into::<Decoder>()?;
+        let mut decoder_guard = decoder.borrow_mut();
+
+        // Copy every field found in the source archive, unless the caller
+        // already picked a subset through `options`.
+        let flags = decoder_guard.decoder.header().flags();
+        let sequence_type = decoder_guard.decoder.sequence_type();
+        let encoder_options = PyDict::new(py);
+        if let Some(opts) = options {
+            encoder_options.update(opts.as_mapping())?;
+        }
+        for (key, flag) in [
+            ("id", Flag::Id),
+            ("comment", Flag::Comment),
+            ("sequence", Flag::Sequence),
+            ("quality", Flag::Quality),
+        ] {
+            if encoder_options.get_item(key)?.is_none() {
+                encoder_options.set_item(key, flags.test(flag))?;
+            }
+        }
+
+        let encoder = Encoder::type_object(py)
+            .call(
+                (dst, SequenceType::from(sequence_type)),
+                Some(&encoder_options),
+            )?
+            .downcast_into::<Encoder>()?;
+        let mut encoder_guard = encoder.borrow_mut();
+
+        while let Some(result) = decoder_guard.deref_mut().decoder.next() {
+            let record = result.map_err(|e| convert_error(py, e, None))?;
+
+            if let Some(ids) = ids.as_ref() {
+                if !record.id.as_deref().is_some_and(|id| ids.contains(id)) {
+                    continue;
+                }
+            }
+            if let Some(predicate) = predicate.as_ref() {
+                let keep = predicate.call1((record.id.as_deref(),))?.extract::<bool>()?;
+                if !keep {
+                    continue;
+                }
+            }
+
+            match encoder_guard.encoder.as_mut() {
+                Some(enc) => enc.push(&record).map_err(|e| convert_error(py, e, None))?,
+                None => return Err(PyRuntimeError::new_err("operation on closed encoder.")),
+            }
+        }
+
+        drop(decoder_guard);
+        Encoder::close(encoder_guard)
+    }
+
     Ok(())
 }
@@ -15,24 +15,49 @@ use pyo3::types::PyInt;
 
 // ---------------------------------------------------------------------------
 
+/// A [`std::io::Error`] payload that keeps the original [`PyErr`] around.
+///
+/// Converting a Python exception straight to an [`IoError`] of a given
+/// [`ErrorKind`](std::io::ErrorKind) (see [`transmute_file_error`]) used to
+/// lose the exception itself: its message, concrete type (a custom
+/// exception subclass, say) and `__cause__` chain. Stashing it here instead
+/// of restoring it immediately lets [`convert_error`](crate::convert_error)
+/// recover and re-raise the exact same exception once the error has
+/// bubbled back up to the `#[pyfunction]`/`#[pymethods]` boundary.
+#[derive(Debug)]
+pub struct PyIoError(pub PyErr);
+
+impl std::fmt::Display for PyIoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for PyIoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
 #[macro_export]
 macro_rules! transmute_file_error {
-    ($self:ident, $e:ident, $msg:expr, $py:expr) => {{
-        // Attempt to transmute the Python OSError to an actual
-        // Rust `std::io::Error` using `from_raw_os_error`.
-        if $e.is_instance_of::<PyOSError>($py) {
-            if let Ok(code) = &$e.value($py).getattr("errno") {
-                if let Ok(n) = code.extract::<i32>() {
-                    return Err(IoError::from_raw_os_error(n));
-                }
-            }
-        }
+    ($self:ident, $e:ident, $py:expr) => {{
+        // Recover the `ErrorKind` an `OSError`'s `errno` would map to, so
+        // callers inspecting `io_error.kind()` still see a meaningful
+        // kind, but keep the exception itself (see `PyIoError`) instead of
+        // discarding it, so it can be re-raised as-is at the pyo3 boundary.
+        let kind = if $e.is_instance_of::<PyOSError>($py) {
+            $e.value($py)
+                .getattr("errno")
+                .ok()
+                .and_then(|code| code.extract::<i32>().ok())
+                .map(|n| IoError::from_raw_os_error(n).kind())
+                .unwrap_or(std::io::ErrorKind::Other)
+        } else {
+            std::io::ErrorKind::Other
+        };
 
-        // if the conversion is not possible for any reason we fail
-        // silently, wrapping the Python error, and returning a
-        // generic Rust error instead.
-        $e.restore($py);
-        Err(IoError::new(std::io::ErrorKind::Other, $msg))
+        return Err(IoError::new(kind, $crate::pyfile::PyIoError($e)));
     }};
 }
 
@@ -92,8 +117,17 @@ impl PyFileRead {
                 .call_method1(py, pyo3::intern!(py, "read"), (buf.len(),))
             {
                 Ok(obj) => {
-                    // Check `fh.read` returned bytes, else raise a `TypeError`.
-                    if let Ok(bytes) = obj.extract::<Bound<PyBytes>>(py) {
+                    // `io.RawIOBase.read` returns `None` instead of bytes
+                    // when the stream is non-blocking and no data is
+                    // available yet: report that as `WouldBlock` instead of
+                    // a hard failure so callers can retry.
+                    if obj.is_none(py) {
+                        Err(IoError::new(
+                            std::io::ErrorKind::WouldBlock,
+                            "fh.read returned None (non-blocking stream)",
+                        ))
+                    } else if let Ok(bytes) = obj.extract::<Bound<PyBytes>>(py) {
+                        // Check `fh.read` returned bytes, else raise a `TypeError`.
                         let b = bytes.as_bytes();
                         (&mut buf[..b.len()]).copy_from_slice(b);
                         Ok(b.len())
@@ -108,7 +142,7 @@ impl PyFileRead {
                     }
                 }
                 Err(e) => {
-                    transmute_file_error!(self, e, "read method failed", py)
+                    transmute_file_error!(self, e, py)
                 }
             }
         })
@@ -128,6 +162,10 @@ impl PyFileRead {
                 .file
                 .call_method1(py, pyo3::intern!(py, "readinto"), (memview,))
             {
+                Ok(n) if n.is_none(py) => Err(IoError::new(
+                    std::io::ErrorKind::WouldBlock,
+                    "fh.readinto returned None (non-blocking stream)",
+                )),
                 Ok(n) => match n.extract::<usize>(py) {
                     Ok(n) => Ok(n),
                     Err(_) => {
@@ -141,7 +179,7 @@ impl PyFileRead {
                     }
                 },
                 Err(e) => {
-                    transmute_file_error!(self, e, "readinto method failed", py)
+                    transmute_file_error!(self, e, py)
                 }
             }
         })
@@ -202,6 +240,20 @@ impl PyFileWrite {
             file: file.clone().unbind().into_any(),
         })
     }
+
+    /// Resize the file to `size` bytes via the Python `truncate` method.
+    pub fn truncate(&mut self, size: u64) -> Result<(), IoError> {
+        Python::with_gil(|py| {
+            match self
+                .file
+                .bind(py)
+                .call_method1(pyo3::intern!(py, "truncate"), (size,))
+            {
+                Ok(_) => Ok(()),
+                Err(e) => transmute_file_error!(self, e, py),
+            }
+        })
+    }
 }
 
 impl Write for PyFileWrite {
@@ -223,10 +275,19 @@ impl Write for PyFileWrite {
                 .call_method1(pyo3::intern!(py, "write"), (memview,))
             {
                 Err(e) => {
-                    transmute_file_error!(self, e, "write method failed", py)
+                    transmute_file_error!(self, e, py)
                 }
                 Ok(obj) => {
-                    if let Ok(n) = obj.extract::<usize>() {
+                    // `io.RawIOBase.write` returns `None` instead of a byte
+                    // count when the stream is non-blocking and the write
+                    // could not be accepted yet: report that as
+                    // `WouldBlock` instead of a hard failure.
+                    if obj.is_none() {
+                        Err(IoError::new(
+                            std::io::ErrorKind::WouldBlock,
+                            "fh.write returned None (non-blocking stream)",
+                        ))
+                    } else if let Ok(n) = obj.extract::<usize>() {
                         Ok(n)
                     } else {
                         let ty = obj.get_type().name()?.to_string();
@@ -246,16 +307,113 @@ impl Write for PyFileWrite {
         Python::with_gil(
             |py| match self.file.bind(py).call_method0(pyo3::intern!(py, "flush")) {
                 Ok(_) => Ok(()),
-                Err(e) => transmute_file_error!(self, e, "flush method failed", py),
+                Err(e) => transmute_file_error!(self, e, py),
             },
         )
     }
 }
 
+impl Seek for PyFileWrite {
+    fn seek(&mut self, seek: SeekFrom) -> Result<u64, IoError> {
+        let (offset, whence) = match seek {
+            SeekFrom::Start(n) => (n as i64, 0),
+            SeekFrom::Current(n) => (n, 1),
+            SeekFrom::End(n) => (n, 2),
+        };
+        Python::with_gil(|py| {
+            match self
+                .file
+                .bind(py)
+                .call_method1(pyo3::intern!(py, "seek"), (offset, whence))
+            {
+                Ok(obj) => {
+                    if let Ok(n) = obj.extract::<u64>() {
+                        Ok(n)
+                    } else {
+                        let ty = obj.get_type().name()?.to_string();
+                        let msg = format!("expected int, found {}", ty);
+                        PyTypeError::new_err(msg).restore(py);
+                        Err(IoError::new(
+                            std::io::ErrorKind::Other,
+                            "fh.seek did not return position",
+                        ))
+                    }
+                }
+                Err(e) => transmute_file_error!(self, e, py),
+            }
+        })
+    }
+}
+
+// ---------------------------------------------------------------------------
+
+/// A zero-copy, read-only view over a Python object exposing the buffer
+/// protocol (`bytes`, `bytearray`, `memoryview`, `mmap.mmap`, etc.).
+///
+/// The buffer is borrowed once in [`PyFileBuffer::from_ref`]; every
+/// subsequent `read`/`seek` just moves a cursor over the already-mapped
+/// memory, without acquiring the GIL or calling back into Python at all.
+pub struct PyFileBuffer {
+    buffer: pyo3::buffer::PyBuffer<u8>,
+    pos: usize,
+}
+
+impl PyFileBuffer {
+    pub fn from_ref<'py>(file: &Bound<'py, PyAny>) -> PyResult<PyFileBuffer> {
+        let buffer = pyo3::buffer::PyBuffer::<u8>::get(file)?;
+        if !buffer.is_c_contiguous() {
+            return Err(PyTypeError::new_err(
+                "buffer is not C-contiguous",
+            ));
+        }
+        Ok(PyFileBuffer { buffer, pos: 0 })
+    }
+
+    fn len(&self) -> usize {
+        self.buffer.item_count()
+    }
+}
+
+impl Read for PyFileBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, IoError> {
+        let n = buf.len().min(self.len().saturating_sub(self.pos));
+        if n > 0 {
+            // SAFETY: `n` was clamped to the number of bytes remaining
+            // between `self.pos` and the end of the buffer, and the
+            // buffer is kept alive for as long as `self.buffer` is.
+            let src = unsafe {
+                std::slice::from_raw_parts((self.buffer.buf_ptr() as *const u8).add(self.pos), n)
+            };
+            buf[..n].copy_from_slice(src);
+            self.pos += n;
+        }
+        Ok(n)
+    }
+}
+
+impl Seek for PyFileBuffer {
+    fn seek(&mut self, seek: SeekFrom) -> Result<u64, IoError> {
+        let pos = match seek {
+            SeekFrom::Start(n) => n as i64,
+            SeekFrom::Current(n) => self.pos as i64 + n,
+            SeekFrom::End(n) => self.len() as i64 + n,
+        };
+        if pos < 0 {
+            return Err(IoError::new(
+                std::io::ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            ));
+        }
+        self.pos = pos as usize;
+        Ok(self.pos as u64)
+    }
+}
+
 // ---------------------------------------------------------------------------
 
 pub enum PyFileReadWrapper {
     PyFile(PyFileRead),
+    Buffer(PyFileBuffer),
     File(File),
 }
 
@@ -263,6 +421,7 @@ impl Read for PyFileReadWrapper {
     fn read(&mut self, buf: &mut [u8]) -> Result<usize, IoError> {
         match self {
             PyFileReadWrapper::PyFile(r) => r.read(buf),
+            PyFileReadWrapper::Buffer(r) => r.read(buf),
             PyFileReadWrapper::File(f) => f.read(buf),
         }
     }
@@ -272,6 +431,7 @@ impl Seek for PyFileReadWrapper {
     fn seek(&mut self, seek: SeekFrom) -> Result<u64, IoError> {
         match self {
             PyFileReadWrapper::PyFile(r) => r.seek(seek),
+            PyFileReadWrapper::Buffer(r) => r.seek(seek),
             PyFileReadWrapper::File(f) => f.seek(seek),
         }
     }
@@ -299,3 +459,22 @@ impl Write for PyFileWriteWrapper {
         }
     }
 }
+
+impl Seek for PyFileWriteWrapper {
+    fn seek(&mut self, seek: SeekFrom) -> Result<u64, IoError> {
+        match self {
+            PyFileWriteWrapper::PyFile(f) => f.seek(seek),
+            PyFileWriteWrapper::File(f) => f.seek(seek),
+        }
+    }
+}
+
+impl PyFileWriteWrapper {
+    /// Resize the underlying file/object to `size` bytes.
+    pub fn truncate(&mut self, size: u64) -> Result<(), IoError> {
+        match self {
+            PyFileWriteWrapper::PyFile(f) => f.truncate(size),
+            PyFileWriteWrapper::File(f) => f.set_len(size),
+        }
+    }
+}
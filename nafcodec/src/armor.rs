@@ -0,0 +1,241 @@
+//! ASCII-armored text container for transporting NAF archives.
+//!
+//! Binary NAF archives cannot travel unmodified through text-only
+//! channels (email bodies, paste buffers, patch files): [`ArmorWriter`]
+//! wraps a byte stream the same way OpenPGP's "radix-64" armor wraps a
+//! binary packet, base64-encoding it, wrapping the result at 64
+//! characters per line, and framing it between `-----BEGIN NAF
+//! ARCHIVE-----`/`-----END NAF ARCHIVE-----` delimiter lines with a
+//! trailing CRC-24 checksum line. [`ArmorReader`] reverses the process,
+//! stripping the framing and whitespace, decoding the base64 body and
+//! verifying the checksum before handing back the raw archive bytes.
+
+use std::io::BufRead;
+use std::io::Error as IoError;
+use std::io::Read;
+use std::io::Write;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::error::Error;
+
+const BEGIN_LINE: &str = "-----BEGIN NAF ARCHIVE-----";
+const END_LINE: &str = "-----END NAF ARCHIVE-----";
+const LINE_WIDTH: usize = 64;
+
+/// The initial value and polynomial of the CRC-24 used by OpenPGP armor,
+/// processing each byte MSB-first. See RFC 4880, section 6.1.
+const CRC24_INIT: u32 = 0x00B7_04CE;
+const CRC24_POLY: u32 = 0x0186_4CFB;
+
+/// Fold one more byte into a running OpenPGP CRC-24 accumulator.
+fn crc24_update(mut crc: u32, byte: u8) -> u32 {
+    crc ^= (byte as u32) << 16;
+    for _ in 0..8 {
+        crc <<= 1;
+        if crc & 0x0100_0000 != 0 {
+            crc ^= CRC24_POLY;
+        }
+    }
+    crc
+}
+
+/// A writer that ASCII-armors every byte written to it.
+///
+/// Bytes are base64-encoded as they arrive, three at a time, and wrapped
+/// at [`LINE_WIDTH`] characters per line; at most two bytes are ever held
+/// back waiting for the rest of their base64 group. Call
+/// [`ArmorWriter::finish`] once all data has been written to flush the
+/// trailing group, append the CRC-24 checksum line and the closing
+/// delimiter, and get the inner writer back.
+pub struct ArmorWriter<W> {
+    inner: W,
+    carry: Vec<u8>,
+    crc: u32,
+    column: usize,
+}
+
+impl<W: Write> ArmorWriter<W> {
+    /// Write the opening delimiter line and start a new armored stream.
+    pub fn new(mut inner: W) -> Result<Self, IoError> {
+        writeln!(inner, "{}", BEGIN_LINE)?;
+        Ok(Self {
+            inner,
+            carry: Vec::with_capacity(2),
+            crc: CRC24_INIT,
+            column: 0,
+        })
+    }
+
+    /// Write already-encoded base64 text, wrapping it at `LINE_WIDTH`.
+    fn write_encoded(&mut self, mut encoded: &[u8]) -> Result<(), IoError> {
+        while !encoded.is_empty() {
+            let take = (LINE_WIDTH - self.column).min(encoded.len());
+            self.inner.write_all(&encoded[..take])?;
+            self.column += take;
+            encoded = &encoded[take..];
+            if self.column == LINE_WIDTH {
+                self.inner.write_all(b"\n")?;
+                self.column = 0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Flush the trailing base64 group, write the CRC-24 checksum line
+    /// and the closing delimiter, and return the inner writer.
+    pub fn finish(mut self) -> Result<W, IoError> {
+        if !self.carry.is_empty() {
+            let encoded = STANDARD.encode(&self.carry);
+            self.write_encoded(encoded.as_bytes())?;
+        }
+        if self.column != 0 {
+            self.inner.write_all(b"\n")?;
+        }
+        let crc = (self.crc & 0x00FF_FFFF).to_be_bytes();
+        writeln!(self.inner, "={}", STANDARD.encode(&crc[1..]))?;
+        writeln!(self.inner, "{}", END_LINE)?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for ArmorWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, IoError> {
+        for &byte in buf {
+            self.crc = crc24_update(self.crc, byte);
+        }
+        self.carry.extend_from_slice(buf);
+
+        let n_groups = self.carry.len() / 3;
+        if n_groups > 0 {
+            let taken = n_groups * 3;
+            let encoded = STANDARD.encode(&self.carry[..taken]);
+            self.write_encoded(encoded.as_bytes())?;
+            self.carry.drain(..taken);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), IoError> {
+        self.inner.flush()
+    }
+}
+
+/// A reader that yields the raw bytes of an ASCII-armored NAF archive.
+///
+/// The framing, whitespace and base64 encoding are all stripped eagerly
+/// by [`ArmorReader::new`], which also verifies the trailing CRC-24
+/// checksum against the decoded bytes; [`Read`] then just serves the
+/// already-decoded buffer.
+pub struct ArmorReader {
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl ArmorReader {
+    /// Parse an armored stream out of `reader`.
+    ///
+    /// Returns [`Error::Armor`] if the opening/closing delimiter lines
+    /// are missing, the checksum line is absent or malformed, or the
+    /// decoded checksum does not match the CRC-24 computed over the
+    /// decoded bytes.
+    pub fn new<R: BufRead>(mut reader: R) -> Result<Self, Error> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(Error::Armor("missing BEGIN delimiter"));
+            }
+            if line.trim_end() == BEGIN_LINE {
+                break;
+            }
+        }
+
+        let mut encoded = String::new();
+        let mut checksum_line = None;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(Error::Armor("missing END delimiter"));
+            }
+            let trimmed = line.trim();
+            if trimmed == END_LINE {
+                break;
+            } else if let Some(rest) = trimmed.strip_prefix('=') {
+                checksum_line = Some(rest.to_string());
+            } else {
+                encoded.push_str(trimmed);
+            }
+        }
+
+        let data = STANDARD
+            .decode(encoded)
+            .map_err(|_| Error::Armor("invalid base64 body"))?;
+
+        let checksum = checksum_line.ok_or(Error::Armor("missing checksum line"))?;
+        let checksum_bytes = STANDARD
+            .decode(checksum)
+            .map_err(|_| Error::Armor("invalid base64 checksum"))?;
+        if checksum_bytes.len() != 3 {
+            return Err(Error::Armor("checksum is not 24 bits wide"));
+        }
+        let expected = u32::from_be_bytes([0, checksum_bytes[0], checksum_bytes[1], checksum_bytes[2]]);
+
+        let actual = data.iter().fold(CRC24_INIT, |crc, &b| crc24_update(crc, b)) & 0x00FF_FFFF;
+        if actual != expected {
+            return Err(Error::Armor("checksum mismatch"));
+        }
+
+        Ok(Self { data, pos: 0 })
+    }
+}
+
+impl Read for ArmorReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, IoError> {
+        let n = buf.len().min(self.data.len() - self.pos);
+        buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_roundtrip() {
+        let payload = b"hello, NAF world! this is a test archive body.".repeat(4);
+
+        let mut writer = ArmorWriter::new(Vec::new()).unwrap();
+        writer.write_all(&payload).unwrap();
+        let armored = writer.finish().unwrap();
+
+        let text = std::str::from_utf8(&armored).unwrap();
+        assert!(text.starts_with(BEGIN_LINE));
+        assert!(text.trim_end().ends_with(END_LINE));
+
+        let mut reader = ArmorReader::new(BufReader::new(Cursor::new(armored))).unwrap();
+        let mut decoded = Vec::new();
+        reader.read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, payload);
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let mut writer = ArmorWriter::new(Vec::new()).unwrap();
+        writer.write_all(b"some data").unwrap();
+        let mut armored = writer.finish().unwrap();
+
+        // Flip a character in the base64 body, just after the BEGIN line,
+        // leaving the framing and checksum line themselves untouched.
+        let body_start = armored.iter().position(|&b| b == b'\n').unwrap() + 1;
+        armored[body_start] = if armored[body_start] == b'A' { b'B' } else { b'A' };
+
+        let err = ArmorReader::new(BufReader::new(Cursor::new(armored))).unwrap_err();
+        assert!(matches!(err, Error::Armor("checksum mismatch")));
+    }
+}
@@ -0,0 +1,197 @@
+//! Optional at-rest encryption for NAF content blocks (the `crypto` feature).
+//!
+//! Layered underneath compression, mirroring the MLA archive format's
+//! raw -> compress -> encrypt pipeline: [`EncryptionLayer`] wraps the
+//! already-Zstandard-compressed bytes written to storage and encrypts them
+//! with AES-256 in CTR mode, authenticating the result with a trailing
+//! HMAC-SHA256 tag. The two keys are independent: both are expanded with
+//! HKDF-SHA256 from a shared secret derived through X25519 Diffie-Hellman
+//! between a fresh ephemeral keypair (generated once per archive) and the
+//! recipient's public key, since the raw Diffie-Hellman output is neither
+//! uniform nor safe to reuse directly across two different primitives.
+//! The ephemeral public key is stored in cleartext in the archive's
+//! extension block (see
+//! [`ExtensionField::Encryption`](crate::extension::ExtensionField::Encryption))
+//! so a decoder holding the recipient's private key can re-derive the same
+//! two keys through [`derive_keys`].
+//!
+//! This module only provides the primitives; wiring [`DecryptionLayer`]
+//! into the block-reading pipeline of [`Decoder`](crate::Decoder) is left
+//! as follow-up work, since every content block reader is constructed
+//! against the raw archive bytes before the extension block (and thus the
+//! keying material) has been read. Until that round trip exists,
+//! [`crate::decoder::DecoderBuilder`] fails with a clear [`Error::Encrypted`]
+//! instead of returning ciphertext as if it were decoded data, and
+//! [`EncoderBuilder::recipient_public_key`](crate::EncoderBuilder::recipient_public_key)
+//! refuses to produce such an archive in the first place, rather than
+//! silently writing one out that nothing (not even this crate) can read back.
+
+use aes::cipher::KeyIvInit;
+use aes::cipher::StreamCipher;
+use hkdf::Hkdf;
+use hmac::Hmac;
+use hmac::Mac;
+use sha2::Sha256;
+use x25519_dalek::EphemeralSecret;
+use x25519_dalek::PublicKey;
+use x25519_dalek::StaticSecret;
+
+use crate::error::Error;
+
+type Aes256Ctr = ctr::Ctr64BE<aes::Aes256>;
+type HmacSha256 = Hmac<Sha256>;
+
+/// A CTR-mode nonce only needs to be unique per key, never reused; since
+/// every archive is encrypted with a freshly generated ephemeral key, a
+/// fixed all-zero nonce never repeats for a given key, so there is no need
+/// to generate and store one per archive.
+const NONCE: [u8; 16] = [0u8; 16];
+
+/// Expand a raw X25519 shared secret into independent encryption and
+/// authentication keys via HKDF-SHA256.
+///
+/// A Diffie-Hellman output is not a uniformly random key and must not be
+/// fed to two different primitives as-is: doing so ties the AES-256
+/// keystream and the HMAC-SHA256 tag to the same key material, so
+/// anything that leaks information about one (a side channel, a chosen-
+/// ciphertext probe against the MAC) leaks information about the other.
+/// HKDF, keyed with the shared secret and two distinct context strings,
+/// produces two keys an attacker cannot correlate.
+fn expand_shared_secret(shared_secret: &[u8; 32]) -> ([u8; 32], [u8; 32]) {
+    let hkdf = Hkdf::<Sha256>::new(None, shared_secret);
+    let mut enc_key = [0u8; 32];
+    let mut mac_key = [0u8; 32];
+    hkdf.expand(b"nafcodec archive encryption key v1", &mut enc_key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    hkdf.expand(b"nafcodec archive authentication key v1", &mut mac_key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    (enc_key, mac_key)
+}
+
+/// The ephemeral keypair and derived keys generated to encrypt one archive.
+#[derive(Clone)]
+pub(crate) struct EncryptionContext {
+    ephemeral_public_key: [u8; 32],
+    enc_key: [u8; 32],
+    mac_key: [u8; 32],
+}
+
+impl std::fmt::Debug for EncryptionContext {
+    /// Redact both symmetric keys; only the (already public) ephemeral key is shown.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptionContext")
+            .field("ephemeral_public_key", &self.ephemeral_public_key)
+            .field("enc_key", &"..")
+            .field("mac_key", &"..")
+            .finish()
+    }
+}
+
+impl EncryptionContext {
+    /// Generate a fresh ephemeral keypair and derive keys with `recipient_public_key`.
+    pub(crate) fn new(recipient_public_key: &[u8; 32]) -> Self {
+        let ephemeral = EphemeralSecret::random_from_rng(rand_core::OsRng);
+        let ephemeral_public_key = PublicKey::from(&ephemeral);
+        let shared = ephemeral.diffie_hellman(&PublicKey::from(*recipient_public_key));
+        let (enc_key, mac_key) = expand_shared_secret(shared.as_bytes());
+        Self {
+            ephemeral_public_key: *ephemeral_public_key.as_bytes(),
+            enc_key,
+            mac_key,
+        }
+    }
+
+    /// Get the ephemeral public key, to be stored in the archive's extension block.
+    pub(crate) fn ephemeral_public_key(&self) -> [u8; 32] {
+        self.ephemeral_public_key
+    }
+
+    /// Get the AES-256-CTR key derived for this archive.
+    pub(crate) fn enc_key(&self) -> [u8; 32] {
+        self.enc_key
+    }
+
+    /// Get the HMAC-SHA256 key derived for this archive.
+    pub(crate) fn mac_key(&self) -> [u8; 32] {
+        self.mac_key
+    }
+}
+
+/// Re-derive the encryption and authentication keys on the decode side.
+///
+/// `private_key` is the recipient's static X25519 private key, and
+/// `ephemeral_public_key` is the per-archive key read from the archive's
+/// extension block (see
+/// [`ExtensionBlock::encryption`](crate::extension::ExtensionBlock::encryption)).
+/// Returns `(enc_key, mac_key)`, matching [`EncryptionContext::enc_key`]/
+/// [`EncryptionContext::mac_key`] on the encode side.
+pub(crate) fn derive_keys(private_key: &[u8; 32], ephemeral_public_key: &[u8; 32]) -> ([u8; 32], [u8; 32]) {
+    let secret = StaticSecret::from(*private_key);
+    let shared = secret.diffie_hellman(&PublicKey::from(*ephemeral_public_key));
+    expand_shared_secret(shared.as_bytes())
+}
+
+/// A writer that encrypts every byte written to it with AES-256-CTR, and
+/// appends a trailing HMAC-SHA256 tag over the ciphertext once
+/// [`finish`](EncryptionLayer::finish) is called.
+pub(crate) struct EncryptionLayer<W> {
+    inner: W,
+    cipher: Aes256Ctr,
+    mac: HmacSha256,
+}
+
+impl<W: std::io::Write> EncryptionLayer<W> {
+    pub(crate) fn new(inner: W, enc_key: &[u8; 32], mac_key: &[u8; 32]) -> Result<Self, Error> {
+        let cipher = Aes256Ctr::new(enc_key.into(), &NONCE.into());
+        let mac =
+            HmacSha256::new_from_slice(mac_key).expect("HMAC-SHA256 accepts keys of any length");
+        Ok(Self { inner, cipher, mac })
+    }
+
+    /// Write the trailing authentication tag and return the inner writer.
+    pub(crate) fn finish(mut self) -> Result<W, Error> {
+        let tag = self.mac.finalize().into_bytes();
+        self.inner.write_all(&tag)?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: std::io::Write> std::io::Write for EncryptionLayer<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut ciphertext = buf.to_vec();
+        self.cipher.apply_keystream(&mut ciphertext);
+        self.mac.update(&ciphertext);
+        self.inner.write_all(&ciphertext)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A reader that decrypts AES-256-CTR ciphertext produced by [`EncryptionLayer`].
+///
+/// Verifying the trailing HMAC-SHA256 tag is left to the caller once the
+/// wrapped reader has yielded all of the plaintext, since this type has no
+/// way on its own to tell the ciphertext apart from the tag that follows
+/// it without also being told the plaintext length.
+pub(crate) struct DecryptionLayer<R> {
+    inner: R,
+    cipher: Aes256Ctr,
+}
+
+impl<R: std::io::Read> DecryptionLayer<R> {
+    pub(crate) fn new(inner: R, enc_key: &[u8; 32]) -> Self {
+        let cipher = Aes256Ctr::new(enc_key.into(), &NONCE.into());
+        Self { inner, cipher }
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for DecryptionLayer<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.cipher.apply_keystream(&mut buf[..n]);
+        Ok(n)
+    }
+}
@@ -2,12 +2,22 @@
 
 // --- MaskUnit ----------------------------------------------------------------
 
-use std::borrow::Cow;
-use std::ops::BitOr;
-use std::ops::BitOrAssign;
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::ops::BitOr;
+use core::ops::BitOrAssign;
+use core::ops::Range;
 
 /// A single masked unit with associated status decoded from the mask block.
+///
+/// Serializes (behind the `serde` feature) as a tagged `{masked: <run
+/// length>}` or `{unmasked: <run length>}` object rather than the default
+/// `{Masked: <run length>}`/`{Unmasked: <run length>}`, to match the
+/// lower-case field names used elsewhere in the crate's serde output (see
+/// [`Flags`]).
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum MaskUnit {
     Masked(u64),
     Unmasked(u64),
@@ -25,24 +35,37 @@ pub enum MaskUnit {
 /// secondary structure in dot-bracket notation, or protein secondary
 /// structure.
 ///
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Record<'a> {
     /// The record identifier (accession number).
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub id: Option<Cow<'a, str>>,
     /// The record comment (description).
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub comment: Option<Cow<'a, str>>,
     /// The record sequence.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub sequence: Option<Cow<'a, str>>,
     /// The record quality string.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub quality: Option<Cow<'a, str>>,
     /// The record sequence length.
     pub length: Option<u64>,
+    /// The soft-masked regions of the sequence, as `[start, end)` ranges.
+    ///
+    /// Only populated when [`DecoderBuilder::mask_intervals`](crate::DecoderBuilder::mask_intervals)
+    /// is enabled; in that mode `sequence` is left in its original case and
+    /// the masked spans the encoder recorded are exposed here instead of
+    /// being applied as lower-casing.
+    pub mask: Option<Vec<Range<usize>>>,
 }
 
 // --- FormatVersion -----------------------------------------------------------
 
 /// The supported format versions inside NAF archives.
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FormatVersion {
     #[default]
     V1 = 1,
@@ -53,6 +76,7 @@ pub enum FormatVersion {
 
 /// The type of sequence stored in a Nucleotide Archive Format file.
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SequenceType {
     #[default]
     Dna = 0,
@@ -188,6 +212,71 @@ impl BitOrAssign<Flag> for Flags {
     }
 }
 
+/// The `serde` representation of [`Flags`]: one named boolean per flag,
+/// matching [`Flag::values`], instead of the raw byte `Flags` stores
+/// internally.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct FlagsRepr {
+    quality: bool,
+    sequence: bool,
+    mask: bool,
+    length: bool,
+    comment: bool,
+    id: bool,
+    title: bool,
+    extended: bool,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Flags {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        FlagsRepr {
+            quality: self.test(Flag::Quality),
+            sequence: self.test(Flag::Sequence),
+            mask: self.test(Flag::Mask),
+            length: self.test(Flag::Length),
+            comment: self.test(Flag::Comment),
+            id: self.test(Flag::Id),
+            title: self.test(Flag::Title),
+            extended: self.test(Flag::Extended),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Flags {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let repr = FlagsRepr::deserialize(deserializer)?;
+        let mut flags = Flags::new();
+        for (set, flag) in [
+            repr.quality,
+            repr.sequence,
+            repr.mask,
+            repr.length,
+            repr.comment,
+            repr.id,
+            repr.title,
+            repr.extended,
+        ]
+        .into_iter()
+        .zip(Flag::values())
+        {
+            if set {
+                flags.set(*flag);
+            }
+        }
+        Ok(flags)
+    }
+}
+
 /// The header section of a Nucleotide Archive Format file.
 ///
 /// Headers are the only mandatory section of NAF files, and contain
@@ -195,6 +284,7 @@ impl BitOrAssign<Flag> for Flags {
 /// the formatting the records during decompression.
 ///
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Header {
     pub(crate) format_version: FormatVersion,
     pub(crate) sequence_type: SequenceType,
@@ -0,0 +1,172 @@
+//! Pluggable block decompression backend.
+//!
+//! Every content block inside a NAF archive is stored as a headerless
+//! (magic-less) Zstandard frame. By default this crate decompresses those
+//! frames with the C-backed `zstd` crate, but that dependency cannot be
+//! built for targets such as `wasm32-unknown-unknown` or bare-metal
+//! embedded platforms. Enabling the `ruzstd` feature swaps in the
+//! pure-Rust `ruzstd` decoder instead, at the cost of some throughput.
+//!
+//! Both backends implement [`Codec`], which is the single point where the
+//! rest of `decoder` depends on a specific compression library: downstream
+//! of [`block_decoder`], a block is just a [`BlockReader`], so `IoSlice`,
+//! `Storage` and the block iteration logic never need to change to support
+//! another backend (e.g. an experimental codec for the non-standard `Text`
+//! sequence stream) — only a new [`Codec`] impl, selected in [`ActiveCodec`],
+//! is needed.
+//!
+//! The `ruzstd` feature on its own does not make this crate buildable for
+//! `no_std + alloc` targets: the rest of `decoder` (in particular `IoSlice`
+//! and the `Decoder`/`DecoderBuilder` plumbing around it) still depends on
+//! `std::fs::File` and `std::sync::RwLock`, neither of which this module
+//! touches. Getting the whole decode path to `no_std` is tracked by
+//! [`crate::io`], which lays the `core`/`alloc`-only groundwork those types
+//! would need to be ported onto.
+
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Seek;
+use std::sync::RwLock;
+
+use super::ioslice::IoSlice;
+use super::Rc;
+use crate::error::Error;
+
+/// A pluggable block decompression backend.
+trait Codec {
+    /// The concrete decompressing reader produced by [`Codec::reader`].
+    type Reader<'z, R: Read + Seek + 'z>: BufRead;
+
+    /// Wrap `slice`, the raw compressed bytes of one block, in a decompressing reader.
+    fn reader<'z, R: Read + Seek + 'z>(
+        slice: IoSlice<R>,
+        buffer_size: usize,
+        dictionary: Option<&[u8]>,
+    ) -> Result<Self::Reader<'z, R>, Error>;
+}
+
+/// The default backend, using the C-backed `zstd` crate.
+#[cfg(not(feature = "ruzstd"))]
+struct ZstdCodec;
+
+#[cfg(not(feature = "ruzstd"))]
+impl Codec for ZstdCodec {
+    type Reader<'z, R: Read + Seek + 'z> = BufReader<zstd::Decoder<'z, BufReader<IoSlice<R>>>>;
+
+    fn reader<'z, R: Read + Seek + 'z>(
+        slice: IoSlice<R>,
+        buffer_size: usize,
+        dictionary: Option<&[u8]>,
+    ) -> Result<Self::Reader<'z, R>, Error> {
+        let mut decoder = match dictionary {
+            Some(dict) => zstd::stream::read::Decoder::with_dictionary(slice, dict)?,
+            None => zstd::stream::read::Decoder::new(slice)?,
+        };
+        decoder.include_magicbytes(false)?;
+        Ok(BufReader::with_capacity(buffer_size, decoder))
+    }
+}
+
+/// The pure-Rust backend, using the `ruzstd` crate (the `ruzstd` feature).
+#[cfg(feature = "ruzstd")]
+struct RuzstdCodec;
+
+#[cfg(feature = "ruzstd")]
+impl Codec for RuzstdCodec {
+    type Reader<'z, R: Read + Seek + 'z> = BufReader<
+        ruzstd::decoding::StreamingDecoder<
+            BufReader<std::io::Chain<std::io::Cursor<[u8; 4]>, IoSlice<R>>>,
+        >,
+    >;
+
+    fn reader<'z, R: Read + Seek + 'z>(
+        slice: IoSlice<R>,
+        buffer_size: usize,
+        dictionary: Option<&[u8]>,
+    ) -> Result<Self::Reader<'z, R>, Error> {
+        // `ruzstd` has no dictionary support yet; reject a dictionary
+        // explicitly rather than silently ignoring it.
+        if dictionary.is_some() {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "dictionaries are not supported with the `ruzstd` backend",
+            )));
+        }
+        // `ruzstd` only decodes complete frames, while NAF blocks omit the
+        // 4-byte magic number to save space, so it must be re-inserted
+        // before the pure-Rust decoder ever sees the stream.
+        const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+        let prefixed = std::io::Cursor::new(ZSTD_MAGIC).chain(slice);
+        let decoder = ruzstd::decoding::StreamingDecoder::new(BufReader::with_capacity(
+            buffer_size,
+            prefixed,
+        ))
+        .map_err(|e| Error::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))?;
+        Ok(BufReader::with_capacity(buffer_size, decoder))
+    }
+}
+
+/// The [`Codec`] selected at build time by the `ruzstd` feature.
+#[cfg(not(feature = "ruzstd"))]
+type ActiveCodec = ZstdCodec;
+#[cfg(feature = "ruzstd")]
+type ActiveCodec = RuzstdCodec;
+
+/// The reader type produced for a single compressed content block, as
+/// decompressed by the [`Codec`] selected at build time.
+pub type BlockReader<'z, R> = <ActiveCodec as Codec>::Reader<'z, R>;
+
+#[cfg(all(test, feature = "ruzstd"))]
+mod tests {
+    use std::io::Read;
+    use std::io::Write;
+
+    use super::*;
+
+    /// A block built by the `ruzstd`-enabled [`block_decoder`] must decode
+    /// to the same bytes as a block compressed the same way the encoder
+    /// does (magic bytes stripped), exercising the magic-byte reinsertion
+    /// shim in [`RuzstdCodec::reader`].
+    #[test]
+    fn ruzstd_backend_round_trips_a_magicless_block() {
+        let payload = b"ACGTACGTACGTACGTACGT";
+
+        let mut compressed = Vec::new();
+        let mut encoder = zstd::Encoder::new(&mut compressed, 0).unwrap();
+        encoder.include_magicbytes(false).unwrap();
+        encoder.write_all(payload).unwrap();
+        encoder.finish().unwrap();
+
+        let end = compressed.len() as u64;
+        let tee = Rc::new(RwLock::new(std::io::Cursor::new(compressed)));
+        let mut reader = block_decoder(tee, 0, end, 4096, None).unwrap();
+
+        let mut decoded = Vec::new();
+        reader.read_to_end(&mut decoded).unwrap();
+        assert_eq!(decoded, payload);
+    }
+
+    #[test]
+    fn ruzstd_backend_rejects_a_dictionary() {
+        let tee = Rc::new(RwLock::new(std::io::Cursor::new(Vec::<u8>::new())));
+        assert!(block_decoder(tee, 0, 0, 4096, Some(b"dict")).is_err());
+    }
+}
+
+/// Open the decompressor for a single content block.
+///
+/// `tee` is the shared reader, and `[start, end)` is the byte range of the
+/// block's compressed Zstandard data within it. The returned reader yields
+/// the decompressed bytes of the block. `dictionary`, if given, must be the
+/// same dictionary that was used to compress the block on the encode side.
+pub fn block_decoder<'z, R: Read + Seek + 'z>(
+    tee: Rc<RwLock<R>>,
+    start: u64,
+    end: u64,
+    buffer_size: usize,
+    dictionary: Option<&[u8]>,
+) -> Result<BlockReader<'z, R>, Error> {
+    let slice = IoSlice::new(tee, start, end);
+    ActiveCodec::reader(slice, buffer_size, dictionary)
+}
@@ -4,20 +4,36 @@ use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
 use std::io::Cursor;
+use std::io::Read;
 use std::io::Seek;
 use std::io::SeekFrom;
 use std::iter::FusedIterator;
+use std::ops::Range;
 use std::path::Path;
 use std::sync::RwLock;
 
+#[cfg(feature = "async")]
+mod asynchronous;
+mod codec;
 mod ioslice;
+mod parallel;
 mod parser;
-mod reader;
-
-use self::ioslice::IoSlice;
+mod random_access;
+pub(crate) mod reader;
+mod stream;
+
+#[cfg(feature = "async")]
+pub use self::asynchronous::AsyncDecoder;
+pub use self::parser::HeaderDecoder;
+pub use self::random_access::RandomAccessDecoder;
+pub use self::random_access::RecordIndex;
+pub use self::stream::StreamDecoder;
+
+use self::codec::BlockReader;
 use self::reader::CStringReader;
 use self::reader::LengthReader;
 use self::reader::MaskReader;
+use self::reader::SectionReader;
 use self::reader::SequenceReader;
 use super::Rc;
 use crate::data::Flag;
@@ -27,9 +43,8 @@ use crate::data::MaskUnit;
 use crate::data::Record;
 use crate::data::SequenceType;
 use crate::error::Error;
+use crate::extension::ExtensionBlock;
 
-/// The wrapper used to decode Zstandard stream.
-type ZstdDecoder<'z, R> = BufReader<zstd::Decoder<'z, BufReader<IoSlice<R>>>>;
 
 /// A builder to configure and initialize a [`Decoder`].
 ///
@@ -57,6 +72,10 @@ pub struct DecoderBuilder {
     sequence: bool,
     quality: bool,
     mask: bool,
+    mask_intervals: bool,
+    dictionary: Option<Vec<u8>>,
+    #[cfg(feature = "crypto")]
+    private_key: Option<[u8; 32]>,
 }
 
 impl DecoderBuilder {
@@ -72,6 +91,10 @@ impl DecoderBuilder {
             sequence: true,
             quality: true,
             mask: true,
+            mask_intervals: false,
+            dictionary: None,
+            #[cfg(feature = "crypto")]
+            private_key: None,
         }
     }
 
@@ -147,11 +170,60 @@ impl DecoderBuilder {
         self
     }
 
+    /// Whether to expose the mask as `[start, end)` ranges instead of case-folding.
+    ///
+    /// By default, masked regions are applied to the decoded sequence by
+    /// lower-casing them in place, and [`Record::mask`] is left empty.
+    /// Enabling this leaves `sequence` in its original case and instead
+    /// populates `Record::mask` with the masked intervals, avoiding an
+    /// `O(n)` rescan of the sequence for case transitions. Has no effect
+    /// unless [`DecoderBuilder::mask`] is also enabled.
+    #[inline]
+    pub fn mask_intervals(&mut self, mask_intervals: bool) -> &mut Self {
+        self.mask_intervals = mask_intervals;
+        self
+    }
+
+    /// Use a precomputed Zstandard dictionary to decode content blocks.
+    ///
+    /// This must be the exact same dictionary that was given to the
+    /// [`EncoderBuilder::dictionary`](crate::EncoderBuilder::dictionary)
+    /// used to produce the archive, as the dictionary itself is not stored
+    /// in the archive.
+    pub fn dictionary(&mut self, dictionary: impl Into<Vec<u8>>) -> &mut Self {
+        self.dictionary = Some(dictionary.into());
+        self
+    }
+
+    /// Provide the recipient's X25519 private key to decrypt an encrypted archive.
+    ///
+    /// Must match the public key passed to
+    /// [`EncoderBuilder::recipient_public_key`](crate::EncoderBuilder::recipient_public_key)
+    /// when the archive was written. Requires the `crypto` feature.
+    ///
+    /// Decoding encrypted archives is not implemented yet (see
+    /// [`crate::crypto`]): every `with_*` constructor below rejects an
+    /// archive carrying an
+    /// [`ExtensionField::Encryption`](crate::extension::ExtensionField::Encryption)
+    /// field with [`Error::Encrypted`], whether or not a private key was
+    /// given here, and whether or not it matches.
+    /// [`EncoderBuilder::recipient_public_key`](crate::EncoderBuilder::recipient_public_key)
+    /// likewise now refuses to produce such an archive, so this currently
+    /// only matters for archives written by some other implementation.
+    #[cfg(feature = "crypto")]
+    pub fn private_key(&mut self, private_key: [u8; 32]) -> &mut Self {
+        self.private_key = Some(private_key);
+        self
+    }
+
     /// Consume the builder to get a decoder reading data from the given buffer.
     pub fn with_bytes<'data, 'z>(
         &self,
         bytes: &'data [u8],
-    ) -> Result<Decoder<'z, BufReader<Cursor<&'data [u8]>>>, Error> {
+    ) -> Result<Decoder<'z, BufReader<Cursor<&'data [u8]>>>, Error>
+    where
+        'data: 'z,
+    {
         self.with_reader(BufReader::new(Cursor::new(bytes)))
     }
 
@@ -166,7 +238,7 @@ impl DecoderBuilder {
     }
 
     /// Consume the builder to get a decoder reading data from `reader`.
-    pub fn with_reader<'z, R: BufRead + Seek>(
+    pub fn with_reader<'z, R: BufRead + Seek + 'z>(
         &self,
         mut reader: R,
     ) -> Result<Decoder<'z, R>, Error> {
@@ -177,11 +249,8 @@ impl DecoderBuilder {
                 reader.consume(consumed);
                 header
             }
-            Err(nom::Err::Incomplete(_)) => {
-                return Err(Error::from(std::io::Error::new(
-                    std::io::ErrorKind::UnexpectedEof,
-                    "failed to read header",
-                )));
+            Err(e @ nom::Err::Incomplete(_)) => {
+                return Err(Error::from(e));
             }
             Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
                 return Err(Error::from(e));
@@ -217,10 +286,13 @@ impl DecoderBuilder {
                     // setup the independent decoder for the block
                     if $use_block {
                         let pos = handle.stream_position()?;
-                        let tee_slice = IoSlice::new(tee, pos, pos + compressed_size);
-                        let mut decoder = zstd::stream::read::Decoder::new(tee_slice)?;
-                        decoder.include_magicbytes(false)?;
-                        $block = Some(BufReader::with_capacity(self.buffer_size, decoder));
+                        $block = Some(self::codec::block_decoder(
+                            tee,
+                            pos,
+                            pos + compressed_size,
+                            self.buffer_size,
+                            self.dictionary.as_deref(),
+                        )?);
                     } else {
                         $block = None;
                     }
@@ -241,6 +313,58 @@ impl DecoderBuilder {
         setup_block!(flags, Sequence, self.sequence, rc, seq_block, seqlen);
         setup_block!(flags, Quality, self.quality, rc, quality_block);
 
+        let extensions = if flags.test(Flag::Extended) {
+            let tee = rc.clone();
+            let (original_size, mut block) = {
+                let mut handle = rc.write().unwrap();
+                let buf = handle.fill_buf()?;
+                let (i, original_size) = self::parser::variable_u64(buf)?;
+                let (i, compressed_size) = self::parser::variable_u64(i)?;
+                let consumed = buf.len() - i.len();
+                handle.consume(consumed);
+                let pos = handle.stream_position()?;
+                let block = self::codec::block_decoder(
+                    tee,
+                    pos,
+                    pos + compressed_size,
+                    self.buffer_size,
+                    None,
+                )?;
+                handle.seek(SeekFrom::Current(compressed_size as i64))?;
+                (original_size, block)
+            };
+            let mut payload = Vec::with_capacity(original_size as usize);
+            block.read_to_end(&mut payload)?;
+            Some(ExtensionBlock::read(&payload)?)
+        } else {
+            None
+        };
+
+        if let Some(ephemeral_public_key) = extensions.as_ref().and_then(ExtensionBlock::encryption) {
+            #[cfg(feature = "crypto")]
+            {
+                let private_key = self.private_key.ok_or(Error::Encrypted(
+                    "archive is encrypted; provide a private key with `DecoderBuilder::private_key`",
+                ))?;
+                // This crate does not implement the decrypt round trip yet
+                // (see `crate::crypto`), and `EncoderBuilder`'s
+                // `recipient_public_key` no longer lets an encoder produce
+                // one either: reject outright instead of handing back
+                // ciphertext as if it were decoded data.
+                let _ = crate::crypto::derive_keys(&private_key, &ephemeral_public_key);
+                return Err(Error::Encrypted(
+                    "reading encrypted archives is not supported yet by this version of the crate",
+                ));
+            }
+            #[cfg(not(feature = "crypto"))]
+            {
+                let _ = ephemeral_public_key;
+                return Err(Error::Encrypted(
+                    "archive is encrypted; rebuild nafcodec with the `crypto` feature to read it",
+                ));
+            }
+        }
+
         Ok(Decoder {
             ids: ids_block.map(CStringReader::new),
             com: com_block.map(CStringReader::new),
@@ -248,10 +372,12 @@ impl DecoderBuilder {
             seq: seq_block.map(|x| SequenceReader::new(x, header.sequence_type())),
             qual: quality_block.map(|x| SequenceReader::new(x, SequenceType::Text)),
             mask: mask_block.map(|x| MaskReader::new(x, seqlen)),
+            mask_intervals: self.mask_intervals,
             n: 0,
             header,
             reader: rc,
             unit: MaskUnit::Unmasked(0),
+            extensions,
         })
     }
 }
@@ -282,17 +408,27 @@ impl Default for DecoderBuilder {
 ///
 /// [`Rc`]: https://doc.rust-lang.org/nightly/std/rc/struct.Rc.html
 /// [`Arc`]: https://doc.rust-lang.org/nightly/std/sync/struct.Arc.html
-pub struct Decoder<'z, R: BufRead + Seek> {
+///
+/// # Serialization
+///
+/// With the `serde` feature enabled, [`Record`] already implements
+/// `serde::Serialize`, so `Decoder`'s `Iterator` directly doubles as a
+/// serde source: feeding the yielded, owned [`Record`]s one at a time
+/// into a streaming serializer (for instance `serde_json::to_writer`)
+/// writes out the whole archive without collecting it into a `Vec` first.
+pub struct Decoder<'z, R: BufRead + Seek + 'z> {
     header: Header,
     reader: Rc<RwLock<R>>,
-    ids: Option<CStringReader<ZstdDecoder<'z, R>>>,
-    com: Option<CStringReader<ZstdDecoder<'z, R>>>,
-    len: Option<LengthReader<ZstdDecoder<'z, R>>>,
-    seq: Option<SequenceReader<ZstdDecoder<'z, R>>>,
-    qual: Option<SequenceReader<ZstdDecoder<'z, R>>>,
-    mask: Option<MaskReader<ZstdDecoder<'z, R>>>,
+    ids: Option<CStringReader<BlockReader<'z, R>>>,
+    com: Option<CStringReader<BlockReader<'z, R>>>,
+    len: Option<LengthReader<BlockReader<'z, R>>>,
+    seq: Option<SequenceReader<BlockReader<'z, R>>>,
+    qual: Option<SequenceReader<BlockReader<'z, R>>>,
+    mask: Option<MaskReader<BlockReader<'z, R>>>,
+    mask_intervals: bool,
     n: usize,
     unit: MaskUnit,
+    extensions: Option<ExtensionBlock>,
 }
 
 impl Decoder<'_, BufReader<File>> {
@@ -306,7 +442,7 @@ impl Decoder<'_, BufReader<File>> {
     }
 }
 
-impl<R: BufRead + Seek> Decoder<'_, R> {
+impl<'z, R: BufRead + Seek + 'z> Decoder<'z, R> {
     /// Create a new decoder from the given reader.
     ///
     /// This constructor is a shortcut for `DecoderBuilder::new().with_reader(reader)`.
@@ -335,11 +471,35 @@ impl<R: BufRead + Seek> Decoder<'_, R> {
         self.header().sequence_type()
     }
 
+    /// Get the index of the next record to be read from the archive.
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.n as u64
+    }
+
+    /// Get the archive-level metadata stored in the extension block, if any.
+    ///
+    /// This is only ever populated if the archive was written with
+    /// [`Flag::Extended`] set, which this crate does if (and only if) at
+    /// least one field was set through
+    /// [`EncoderBuilder`](crate::EncoderBuilder)'s extension methods.
+    #[inline]
+    pub fn extensions(&self) -> Option<&ExtensionBlock> {
+        self.extensions.as_ref()
+    }
+
     /// Extract the internal reader.
     ///
     /// Note that the internal reader may have been advanced even if no
     /// records were obtained from the decoder yet, since at least the header
-    /// needs to be decoded to obtain a working decoder.
+    /// needs to be decoded to obtain a working decoder. The decoder never
+    /// reads past the end of its own archive, so the reader is left
+    /// positioned right after the last content block: pass `R = &mut T`
+    /// to [`Decoder::new`]/[`DecoderBuilder::with_reader`] (`T: BufRead +
+    /// Seek` provides those impls for `&mut T` too) to get `T` back through
+    /// `into_inner` and keep reading, e.g. to decode several NAF archives
+    /// concatenated one after another, or an application-specific trailer
+    /// following the archive.
     pub fn into_inner(self) -> R {
         let reader = self.reader.clone();
         drop(self);
@@ -349,6 +509,66 @@ impl<R: BufRead + Seek> Decoder<'_, R> {
             .expect("lock shouldn't be poisoned")
     }
 
+    /// Fetch the `index`-th record of the archive, skipping over the ones before it.
+    ///
+    /// Unlike [`Iterator::next`], this does not require the caller to
+    /// have consumed every record leading up to `index` through the
+    /// iterator API; `Decoder` does the skipping internally, without
+    /// materializing the intermediate [`Record`] values. Note that
+    /// `index` must still be greater than or equal to the position the
+    /// decoder is currently at: the underlying content blocks are plain
+    /// Zstandard streams and can only be read forward, so rewinding to
+    /// an already-consumed record requires creating a new `Decoder`.
+    pub fn record(&mut self, index: u64) -> Result<Record<'static>, Error> {
+        if index < self.n as u64 {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "cannot seek backwards to an already-consumed record",
+            )));
+        }
+        if index >= self.header.number_of_sequences() {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "record index out of bounds",
+            )));
+        }
+        while (self.n as u64) < index {
+            self.skip_record()?;
+        }
+        self.next_record()
+    }
+
+    /// Skip over the next record without allocating its fields.
+    fn skip_record(&mut self) -> Result<(), Error> {
+        if let Some(r) = self.ids.as_mut() {
+            let _ = r.next().transpose()?;
+        }
+        if let Some(r) = self.com.as_mut() {
+            let _ = r.next().transpose()?;
+        }
+        let length = self.len.as_mut().and_then(|r| r.next()).transpose()?;
+        if let Some(l) = length {
+            if let Some(r) = self.seq.as_mut() {
+                let _ = r.next(l)?;
+            }
+            if let Some(r) = self.qual.as_mut() {
+                let _ = r.next(l)?;
+            }
+            if self.mask.is_some() {
+                // Keep the run-length mask cursor in sync with the
+                // sequence cursor: `mask_sequence` advances it based on
+                // the length of the string it is given, so a throwaway
+                // buffer of the right size is needed even though its
+                // contents (and the lower-casing applied to it) are
+                // discarded immediately after.
+                let mut discard = " ".repeat(l as usize);
+                self.mask_sequence(&mut discard)?;
+            }
+        }
+        self.n += 1;
+        Ok(())
+    }
+
     /// Attempt to read the next record from the archive.
     ///
     /// This function expects that a record is available; use `Decoder::next`
@@ -357,34 +577,57 @@ impl<R: BufRead + Seek> Decoder<'_, R> {
         let id = self
             .ids
             .as_mut()
-            .and_then(|r| r.next())
+            .map(|r| r.read_next())
             .transpose()?
+            .flatten()
             .map(|id| id.into_string().map(Cow::Owned).expect("TODO"));
         let comment = self
             .com
             .as_mut()
-            .and_then(|r| r.next())
+            .map(|r| r.read_next())
             .transpose()?
+            .flatten()
             .map(|com| com.into_string().map(Cow::Owned).expect("TODO"));
-        let length = self.len.as_mut().and_then(|r| r.next()).transpose()?;
+        let length = self
+            .len
+            .as_mut()
+            .map(|r| r.read_next())
+            .transpose()?
+            .flatten();
 
         let mut sequence: Option<Cow<'static, str>> = None;
         let mut quality = None;
+        let mut mask = None;
         if let Some(l) = length {
-            sequence = self
-                .seq
-                .as_mut()
-                .map(|r| r.next(l))
-                .transpose()?
-                .map(Cow::Owned);
+            // when case-folding masked regions into the sequence, fold them
+            // in as each block is decoded (`next_masked`) rather than
+            // decoding the whole record first and then making a second
+            // pass over it to lower-case the masked runs.
+            sequence = if !self.mask_intervals && self.mask.is_some() {
+                match self.seq.as_mut() {
+                    Some(r) => {
+                        let unit = &mut self.unit;
+                        let mask_reader = self.mask.as_mut().unwrap();
+                        Some(r.next_masked(l, unit, mask_reader)?)
+                    }
+                    None => None,
+                }
+                .map(Cow::Owned)
+            } else {
+                self.seq
+                    .as_mut()
+                    .map(|r| r.next(l))
+                    .transpose()?
+                    .map(Cow::Owned)
+            };
             quality = self
                 .qual
                 .as_mut()
                 .map(|r| r.next(l))
                 .transpose()?
                 .map(Cow::Owned);
-            if let Some(seq) = sequence.as_mut() {
-                self.mask_sequence(seq.to_mut())?;
+            if self.mask_intervals {
+                mask = self.mask_ranges(l)?;
             }
         }
 
@@ -395,9 +638,73 @@ impl<R: BufRead + Seek> Decoder<'_, R> {
             sequence,
             quality,
             length,
+            mask,
         })
     }
 
+    /// Walk `length` positions of the mask block, collecting masked spans.
+    ///
+    /// Unlike [`Decoder::mask_sequence`], this does not require a decoded
+    /// sequence string: it only advances the shared mask cursor and
+    /// records the `[start, end)` ranges (relative to the start of the
+    /// current record) that were masked, merging adjacent ranges split
+    /// across mask units.
+    fn mask_ranges(&mut self, length: u64) -> Result<Option<Vec<Range<usize>>>, Error> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        if length == 0 || self.mask.is_none() {
+            return Ok(self.mask.is_some().then_some(ranges));
+        }
+
+        let mut mask = self.unit.clone();
+        let mut offset = 0u64;
+        let mut remaining = length;
+        let mask_reader = self.mask.as_mut().unwrap();
+
+        loop {
+            match mask {
+                MaskUnit::Masked(n) => {
+                    let consumed = n.min(remaining);
+                    ranges.push(offset as usize..(offset + consumed) as usize);
+                    if n < remaining {
+                        offset += n;
+                        remaining -= n;
+                    } else {
+                        self.unit = MaskUnit::Masked(n - remaining);
+                        break;
+                    }
+                }
+                MaskUnit::Unmasked(n) => {
+                    if n < remaining {
+                        offset += n;
+                        remaining -= n;
+                    } else {
+                        self.unit = MaskUnit::Unmasked(n - remaining);
+                        break;
+                    }
+                }
+            }
+            mask = match mask_reader.next() {
+                Some(Ok(x)) => x,
+                Some(Err(e)) => return Err(Error::Io(e)),
+                None => {
+                    return Err(Error::Io(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "failed to get mask unit",
+                    )))
+                }
+            };
+        }
+
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for r in ranges {
+            match merged.last_mut() {
+                Some(last) if last.end == r.start => last.end = r.end,
+                _ => merged.push(r),
+            }
+        }
+        Ok(Some(merged))
+    }
+
     /// Attempt to mask some regions of the given sequence.
     fn mask_sequence(&mut self, sequence: &mut str) -> Result<(), Error> {
         let mut mask = self.unit.clone();
@@ -441,7 +748,7 @@ impl<R: BufRead + Seek> Decoder<'_, R> {
     }
 }
 
-impl<R: BufRead + Seek> Iterator for Decoder<'_, R> {
+impl<'z, R: BufRead + Seek + 'z> Iterator for Decoder<'z, R> {
     type Item = Result<Record<'static>, Error>;
     fn next(&mut self) -> Option<Self::Item> {
         if self.n as u64 >= self.header.number_of_sequences() {
@@ -456,9 +763,9 @@ impl<R: BufRead + Seek> Iterator for Decoder<'_, R> {
     }
 }
 
-impl<R: BufRead + Seek> ExactSizeIterator for Decoder<'_, R> {}
+impl<'z, R: BufRead + Seek + 'z> ExactSizeIterator for Decoder<'z, R> {}
 
-impl<R: BufRead + Seek> FusedIterator for Decoder<'_, R> {}
+impl<'z, R: BufRead + Seek + 'z> FusedIterator for Decoder<'z, R> {}
 
 #[cfg(test)]
 mod tests {
@@ -470,7 +777,7 @@ mod tests {
     fn error_empty() {
         match Decoder::new(std::io::Cursor::new(b"")) {
             Ok(_decoder) => panic!("unexpected success"),
-            Err(Error::Io(e)) => assert!(matches!(e.kind(), std::io::ErrorKind::UnexpectedEof)),
+            Err(Error::Incomplete(_)) => (),
             Err(e) => panic!("unexpected error: {:?}", e),
         }
     }
@@ -503,6 +810,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn mask_intervals() {
+        const ARCHIVE: &[u8] = include_bytes!("../../../data/masked.naf");
+        let decoder = DecoderBuilder::new()
+            .mask_intervals(true)
+            .with_reader(std::io::Cursor::new(ARCHIVE))
+            .unwrap();
+        let records = decoder.collect::<Result<Vec<_>, _>>().unwrap();
+
+        // with interval masking enabled, the sequence is left in its
+        // original (upper) case...
+        let total_masked: usize = records
+            .iter()
+            .map(|r| {
+                assert!(r
+                    .sequence
+                    .as_deref()
+                    .unwrap()
+                    .chars()
+                    .all(|c| !c.is_ascii_lowercase()));
+                r.mask.as_ref().unwrap().iter().map(|r| r.len()).sum::<usize>()
+            })
+            .sum();
+        // ...while the masked bases reported as intervals match the two
+        // masked runs from the `masks` test (19 and 39 bases long)
+        assert_eq!(total_masked, 19 + 39);
+    }
+
     #[test]
     fn skip_sequence() {
         let decoder = DecoderBuilder::new()
@@ -513,4 +848,44 @@ mod tests {
             assert!(record.sequence.is_none());
         }
     }
+
+    #[test]
+    fn record() {
+        let mut decoder = Decoder::new(std::io::Cursor::new(ARCHIVE)).unwrap();
+        let all = Decoder::new(std::io::Cursor::new(ARCHIVE))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let r5 = decoder.record(5).unwrap();
+        assert_eq!(r5.id, all[5].id);
+        assert_eq!(r5.sequence, all[5].sequence);
+
+        // indices before the current position cannot be re-fetched
+        assert!(decoder.record(0).is_err());
+    }
+
+    #[test]
+    fn concatenated() {
+        let mut buffer = ARCHIVE.to_vec();
+        buffer.extend_from_slice(ARCHIVE);
+        buffer.push(b'!');
+        let mut cursor = std::io::Cursor::new(buffer);
+
+        let first = Decoder::new(&mut cursor)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(first.len(), 12);
+
+        let second = Decoder::new(&mut cursor)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(second, first);
+
+        let mut trailer = [0u8; 1];
+        std::io::Read::read_exact(&mut cursor, &mut trailer).unwrap();
+        assert_eq!(&trailer, b"!");
+    }
 }
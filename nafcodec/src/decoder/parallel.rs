@@ -0,0 +1,164 @@
+//! A multi-threaded block reader built on top of [`StreamDecoder`].
+
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+
+use super::stream::read_block;
+use super::stream::StreamDecoder;
+use crate::data::Flag;
+use crate::error::Error;
+use crate::DecoderBuilder;
+
+/// The on-disk location of a single content block, recorded while scanning
+/// the archive sequentially, before any block is actually decompressed.
+struct BlockInfo {
+    offset: u64,
+    original_size: u64,
+    compressed_size: u64,
+}
+
+/// Decompress the content block located at `offset` in the file at `path`.
+///
+/// Opening an independent handle per block (instead of sharing one through
+/// the usual `Rc<RwLock<R>>`) is what lets several blocks be decompressed
+/// on different threads at once: there is no lock to contend on, and each
+/// handle only ever seeks to the one position it was given.
+fn read_block_at(
+    path: &Path,
+    offset: u64,
+    original_size: u64,
+    compressed_size: u64,
+) -> Result<Vec<u8>, Error> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut compressed = vec![0u8; compressed_size as usize];
+    std::io::Read::read_exact(&mut file, &mut compressed)?;
+    read_block(&compressed, original_size)
+}
+
+impl DecoderBuilder {
+    /// Consume the builder to get a decoder reading the file at `path`, decompressing
+    /// content blocks in parallel.
+    ///
+    /// The id/comment/length/mask/sequence/quality blocks of a NAF archive
+    /// are disjoint byte ranges that do not depend on one another, so they
+    /// can each be decompressed on their own thread instead of serially.
+    /// This opens its own file handle per block (re-opening `path` rather
+    /// than sharing one, so the blocks are decompressed independently),
+    /// spreads the work over up to `threads` worker threads, and zips the
+    /// results back together once every requested block has been fetched.
+    ///
+    /// Unlike [`DecoderBuilder::with_path`], this reads the whole archive
+    /// into memory up front and returns a [`StreamDecoder`], which can
+    /// only be walked forward: the parallel decompression only pays off
+    /// when every block is going to be decoded anyway, which is also the
+    /// case where forward-only iteration is not a limitation.
+    pub fn with_path_threaded<P: AsRef<Path>>(
+        &self,
+        path: P,
+        threads: usize,
+    ) -> Result<StreamDecoder, Error> {
+        let path = path.as_ref();
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let buffer = reader.fill_buf()?;
+        let header = match super::parser::header(buffer) {
+            Ok((i, header)) => {
+                let consumed = buffer.len() - i.len();
+                reader.consume(consumed);
+                header
+            }
+            Err(e @ nom::Err::Incomplete(_)) => {
+                return Err(Error::from(e));
+            }
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                return Err(Error::from(e));
+            }
+        };
+
+        if header.flags().test(Flag::Title) {
+            let buf = reader.fill_buf()?;
+            let (i, _title) = super::parser::title(buf)?;
+            let consumed = buf.len() - i.len();
+            reader.consume(consumed);
+        }
+
+        let flags = header.flags();
+        macro_rules! scan_block {
+            ($flag:ident) => {{
+                if flags.test(Flag::$flag) {
+                    let buf = reader.fill_buf()?;
+                    let (i, original_size) = super::parser::variable_u64(buf)?;
+                    let (i, compressed_size) = super::parser::variable_u64(i)?;
+                    let consumed = buf.len() - i.len();
+                    reader.consume(consumed);
+                    let offset = reader.stream_position()?;
+                    reader.seek(SeekFrom::Current(compressed_size as i64))?;
+                    Some(BlockInfo {
+                        offset,
+                        original_size,
+                        compressed_size,
+                    })
+                } else {
+                    None
+                }
+            }};
+        }
+
+        let ids_info = scan_block!(Id);
+        let com_info = scan_block!(Comment);
+        let len_info = scan_block!(Length);
+        let mask_info = scan_block!(Mask);
+        let seq_info = scan_block!(Sequence);
+        let qual_info = scan_block!(Quality);
+        let seqlen = seq_info.as_ref().map_or(0, |info| info.original_size);
+
+        // Each entry is `(requested, block)`; only requested blocks with a
+        // known location are actually decompressed.
+        let jobs: [(bool, Option<BlockInfo>); 6] = [
+            (self.id, ids_info),
+            (self.comment, com_info),
+            (true, len_info),
+            (self.mask, mask_info),
+            (self.sequence, seq_info),
+            (self.quality, qual_info),
+        ];
+
+        let mut blocks: [Option<Vec<u8>>; 6] = Default::default();
+        let threads = threads.max(1);
+        std::thread::scope(|scope| -> Result<(), Error> {
+            let mut pending = Vec::new();
+            for (i, (requested, info)) in jobs.iter().enumerate() {
+                if let (true, Some(info)) = (*requested, info) {
+                    pending.push((i, info.offset, info.original_size, info.compressed_size));
+                }
+            }
+            for batch in pending.chunks(threads) {
+                let handles: Vec<_> = batch
+                    .iter()
+                    .map(|&(i, offset, original_size, compressed_size)| {
+                        (
+                            i,
+                            scope.spawn(move || {
+                                read_block_at(path, offset, original_size, compressed_size)
+                            }),
+                        )
+                    })
+                    .collect();
+                for (i, handle) in handles {
+                    blocks[i] = Some(handle.join().expect("worker thread should not panic")?);
+                }
+            }
+            Ok(())
+        })?;
+
+        let [ids, com, len, mask_buf, seq, qual] = blocks;
+        Ok(StreamDecoder::from_blocks(
+            header, ids, com, len, mask_buf, seq, seqlen, qual,
+        ))
+    }
+}
@@ -1,3 +1,5 @@
+use std::task::Poll;
+
 use nom::IResult;
 use nom::Parser;
 
@@ -6,6 +8,7 @@ use crate::data::Flags;
 use crate::data::FormatVersion;
 use crate::data::Header;
 use crate::data::SequenceType;
+use crate::error::Error;
 
 fn is_printable(&byte: &u8) -> bool {
     (0x20..=0x7E).contains(&byte)
@@ -138,6 +141,100 @@ pub fn title(i: &[u8]) -> IResult<&[u8], &str> {
     Ok((i, text))
 }
 
+// --- HeaderDecoder -----------------------------------------------------------
+
+/// What [`HeaderDecoder`] is still waiting to parse.
+enum HeaderDecoderState {
+    /// Waiting for enough bytes to parse the [`Header`] itself.
+    Header,
+    /// The header is parsed; waiting for the title, if [`Flag::Title`] is
+    /// set (otherwise this state resolves immediately on the next `feed`).
+    Title(Header),
+}
+
+/// A push-based, incremental parser for the archive header and title.
+///
+/// [`header`] and [`title`] are written with `nom::bytes::streaming`
+/// combinators and already return `Err::Incomplete` rather than an error
+/// when handed a buffer that ends mid-field; every caller in this crate
+/// just treats that as fatal, though, because they always hand the parser
+/// a complete [`BufRead::fill_buf`](std::io::BufRead::fill_buf) slice. For
+/// a source that only delivers data in fragments (a socket, for example),
+/// `HeaderDecoder` is the state machine that uses `Incomplete` as intended:
+/// [`feed`](Self::feed) appends whatever bytes have just arrived to an
+/// internal buffer and re-runs the streaming parsers from the start, until
+/// there is enough input to produce a [`Header`] (and its title, if
+/// present).
+pub struct HeaderDecoder {
+    buffer: Vec<u8>,
+    state: HeaderDecoderState,
+}
+
+impl HeaderDecoder {
+    /// Create a decoder with nothing fed to it yet.
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            state: HeaderDecoderState::Header,
+        }
+    }
+
+    /// Add `bytes` to the internal buffer and try to make progress.
+    ///
+    /// Returns `Poll::Pending` if there is still not enough input to parse
+    /// a complete header (and title, if [`Flag::Title`] is set), or
+    /// `Poll::Ready` with the header and optional title once there is.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<Poll<(Header, Option<String>)>, Error> {
+        self.buffer.extend_from_slice(bytes);
+
+        if let HeaderDecoderState::Header = self.state {
+            match self::header(&self.buffer) {
+                Ok((i, header)) => {
+                    let consumed = self.buffer.len() - i.len();
+                    self.buffer.drain(..consumed);
+                    self.state = HeaderDecoderState::Title(header);
+                }
+                Err(nom::Err::Incomplete(_)) => return Ok(Poll::Pending),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let header = match &self.state {
+            HeaderDecoderState::Header => unreachable!("just resolved above"),
+            HeaderDecoderState::Title(header) => header,
+        };
+
+        if !header.flags().test(Flag::Title) {
+            let header = match std::mem::replace(&mut self.state, HeaderDecoderState::Header) {
+                HeaderDecoderState::Title(header) => header,
+                HeaderDecoderState::Header => unreachable!("just matched above"),
+            };
+            return Ok(Poll::Ready((header, None)));
+        }
+
+        match self::title(&self.buffer) {
+            Ok((i, text)) => {
+                let title = text.to_string();
+                let consumed = self.buffer.len() - i.len();
+                self.buffer.drain(..consumed);
+                let header = match std::mem::replace(&mut self.state, HeaderDecoderState::Header) {
+                    HeaderDecoderState::Title(header) => header,
+                    HeaderDecoderState::Header => unreachable!("just matched above"),
+                };
+                Ok(Poll::Ready((header, Some(title))))
+            }
+            Err(nom::Err::Incomplete(_)) => Ok(Poll::Pending),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl Default for HeaderDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 mod tests {
 
     #[test]
@@ -149,4 +246,29 @@ mod tests {
         assert_eq!(h.number_of_sequences(), 32);
         assert_eq!(i, b"");
     }
+
+    #[test]
+    fn header_decoder_fed_one_byte_at_a_time() {
+        use std::task::Poll;
+
+        const HEADER: [u8; 8] = [0x01, 0xF9, 0xEC, 0x01, 0x3E, 0x20, 0x3C, 0x20];
+
+        let mut decoder = super::HeaderDecoder::new();
+        let mut result = None;
+        for &byte in HEADER.iter() {
+            match decoder.feed(&[byte]).unwrap() {
+                Poll::Pending => continue,
+                Poll::Ready(ready) => {
+                    result = Some(ready);
+                    break;
+                }
+            }
+        }
+
+        let (header, title) = result.expect("header should be ready after the last byte");
+        assert_eq!(header.name_separator(), ' ');
+        assert_eq!(header.line_length(), 60);
+        assert_eq!(header.number_of_sequences(), 32);
+        assert_eq!(title, None);
+    }
 }
@@ -0,0 +1,175 @@
+//! Index-backed random access into a single record of an archive.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::ops::Range;
+use std::path::Path;
+
+use super::Decoder;
+use super::DecoderBuilder;
+use crate::data::Record;
+use crate::error::Error;
+
+/// The byte ranges of a single record's fields within their content block.
+#[derive(Debug, Clone, Default)]
+struct RecordSpan {
+    id: Option<Range<u64>>,
+    comment: Option<Range<u64>>,
+    data: Option<Range<u64>>,
+}
+
+/// A precomputed record index built from an archive's length, id and
+/// comment blocks.
+///
+/// The id/comment/length blocks are usually tiny compared to the
+/// sequence/quality blocks, so decoding them up front to build a
+/// cumulative prefix-sum of byte ranges is cheap relative to decoding
+/// every record. [`RandomAccessDecoder`] uses this index to validate a
+/// record number and report the byte range it occupies without having
+/// to materialize the fields of the records coming before it.
+#[derive(Debug, Clone)]
+pub struct RecordIndex {
+    spans: Vec<RecordSpan>,
+}
+
+impl RecordIndex {
+    fn build<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut decoder = DecoderBuilder::new()
+            .sequence(false)
+            .quality(false)
+            .mask(false)
+            .with_path(path)?;
+
+        let mut spans = Vec::with_capacity(decoder.header().number_of_sequences() as usize);
+        let mut id_offset = 0u64;
+        let mut com_offset = 0u64;
+        let mut data_offset = 0u64;
+
+        while let Some(result) = decoder.next() {
+            let record = result?;
+            let id = record.id.as_deref().map(|s| {
+                // content blocks are C-strings: the identifier itself,
+                // plus the trailing NUL byte that separates records.
+                let range = id_offset..id_offset + s.len() as u64;
+                id_offset += s.len() as u64 + 1;
+                range
+            });
+            let comment = record.comment.as_deref().map(|s| {
+                let range = com_offset..com_offset + s.len() as u64;
+                com_offset += s.len() as u64 + 1;
+                range
+            });
+            let data = record.length.map(|length| {
+                let range = data_offset..data_offset + length;
+                data_offset += length;
+                range
+            });
+            spans.push(RecordSpan { id, comment, data });
+        }
+
+        Ok(Self { spans })
+    }
+
+    /// Get the number of records in the index.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Check whether the index has no records.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Get the byte range of the `index`-th record's identifier.
+    pub fn id_range(&self, index: usize) -> Option<Range<u64>> {
+        self.spans.get(index)?.id.clone()
+    }
+
+    /// Get the byte range of the `index`-th record's comment.
+    pub fn comment_range(&self, index: usize) -> Option<Range<u64>> {
+        self.spans.get(index)?.comment.clone()
+    }
+
+    /// Get the byte range occupied by the `index`-th record's sequence
+    /// (and, equivalently, quality) data.
+    pub fn data_range(&self, index: usize) -> Option<Range<u64>> {
+        self.spans.get(index)?.data.clone()
+    }
+}
+
+/// A decoder that extracts a single record by index using a [`RecordIndex`].
+///
+/// This builds on top of [`Decoder::record`], adding upfront index
+/// construction (see [`RecordIndex`]) so that an out-of-bounds index can
+/// be rejected, and the byte range of a record's fields can be inspected,
+/// without decoding anything beyond the id/comment/length blocks.
+///
+/// # Limitations
+///
+/// Content blocks are plain Zstandard streams, which cannot be seeked
+/// into mid-frame: fetching record `N` still requires decompressing (and
+/// discarding) every sequence/quality byte belonging to records before
+/// it, same as [`Decoder::record`]. Avoiding that entirely requires
+/// encoding content blocks as a sequence of independent Zstandard frames
+/// with a frame-offset table, so only the frame containing record `N`
+/// needs to be decompressed; this crate does not produce archives in
+/// that layout yet, so `RandomAccessDecoder` is for now a safer,
+/// index-validated wrapper around the existing skip-ahead decoder
+/// rather than a true constant-time seek.
+pub struct RandomAccessDecoder {
+    decoder: Decoder<'static, BufReader<File>>,
+    index: RecordIndex,
+}
+
+impl RandomAccessDecoder {
+    /// Build a random-access decoder over the archive at `path`.
+    pub fn with_path<P: AsRef<Path>>(builder: &DecoderBuilder, path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let index = RecordIndex::build(path)?;
+        let decoder = builder.with_path(path)?;
+        Ok(Self { decoder, index })
+    }
+
+    /// Get the index built for this archive.
+    #[inline]
+    pub fn index(&self) -> &RecordIndex {
+        &self.index
+    }
+
+    /// Get the number of records in the archive.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Check whether the archive has no records.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Fetch the `index`-th record of the archive.
+    pub fn record(&mut self, index: u64) -> Result<Record<'static>, Error> {
+        if index as usize >= self.index.len() {
+            return Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "record index out of bounds",
+            )));
+        }
+        self.decoder.record(index)
+    }
+}
+
+impl DecoderBuilder {
+    /// Consume the builder to get a random-access decoder over the file at `path`.
+    ///
+    /// This is a shortcut for [`RandomAccessDecoder::with_path`].
+    pub fn with_path_indexed<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<RandomAccessDecoder, Error> {
+        RandomAccessDecoder::with_path(self, path)
+    }
+}
@@ -0,0 +1,344 @@
+//! A forward-only decoder for non-seekable sources.
+
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Cursor;
+use std::io::Read;
+use std::iter::FusedIterator;
+
+use super::reader::CStringReader;
+use super::reader::LengthReader;
+use super::reader::MaskReader;
+use super::reader::SequenceReader;
+use crate::data::Flag;
+use crate::data::Header;
+use crate::data::MaskUnit;
+use crate::data::Record;
+use crate::data::SequenceType;
+use crate::error::Error;
+use crate::DecoderBuilder;
+
+/// Decompress a single content block into an owned, in-memory buffer.
+///
+/// Takes the already-extracted `compressed` bytes of the block, rather
+/// than reading straight from the shared stream: the zstd decoder pulls
+/// its own, much larger input buffer out of whatever reader it is given,
+/// so decoding in place from the stream would silently over-read past
+/// the end of the block and desync every block that follows (the
+/// `async` sibling's `inflate_block` bounds its input the same way, for
+/// the same reason).
+pub(super) fn read_block(compressed: &[u8], original_size: u64) -> Result<Vec<u8>, Error> {
+    let mut decoder = zstd::stream::read::Decoder::new(compressed)?;
+    decoder.include_magicbytes(false)?;
+    let mut buffer = Vec::with_capacity(original_size as usize);
+    decoder.read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+/// A decoder for Nucleotide Archive Format streams without random access.
+///
+/// Unlike [`Decoder`](super::Decoder), which requires `R: Seek` so it can
+/// jump between the id/comment/length/mask/sequence/quality blocks,
+/// `StreamDecoder` consumes a plain [`BufRead`] in a single forward pass:
+/// every content block is fully decompressed into an owned buffer, in the
+/// order it appears on disk, before any record is produced. This trades
+/// memory (the whole archive is held decompressed at once) for the
+/// ability to decode data arriving from a pipe, a socket, or standard
+/// input, none of which support seeking.
+pub struct StreamDecoder {
+    header: Header,
+    ids: Option<CStringReader<Cursor<Vec<u8>>>>,
+    com: Option<CStringReader<Cursor<Vec<u8>>>>,
+    len: Option<LengthReader<Cursor<Vec<u8>>>>,
+    mask: Option<MaskReader<Cursor<Vec<u8>>>>,
+    seq: Option<SequenceReader<Cursor<Vec<u8>>>>,
+    qual: Option<SequenceReader<Cursor<Vec<u8>>>>,
+    n: usize,
+    unit: MaskUnit,
+}
+
+impl StreamDecoder {
+    /// Create a new stream decoder by reading a whole archive from `reader`.
+    ///
+    /// This constructor is a shortcut for
+    /// `DecoderBuilder::new().with_stream(reader)`.
+    pub fn new<R: Read>(reader: R) -> Result<Self, Error> {
+        DecoderBuilder::new().with_stream(reader)
+    }
+
+    /// Assemble a stream decoder from already-decompressed content blocks.
+    ///
+    /// This is the shared back end for both [`DecoderBuilder::with_stream`]
+    /// (which inflates each block synchronously) and the `async`-gated
+    /// decoder (which fetches the same blocks using non-blocking I/O):
+    /// once the compressed bytes of a block have been read and inflated,
+    /// assembling records from them is identical in both cases.
+    pub(crate) fn from_blocks(
+        header: Header,
+        ids: Option<Vec<u8>>,
+        com: Option<Vec<u8>>,
+        len: Option<Vec<u8>>,
+        mask_buf: Option<Vec<u8>>,
+        seq: Option<Vec<u8>>,
+        seqlen: u64,
+        qual: Option<Vec<u8>>,
+    ) -> Self {
+        Self {
+            ids: ids.map(Cursor::new).map(CStringReader::new),
+            com: com.map(Cursor::new).map(CStringReader::new),
+            len: len.map(Cursor::new).map(LengthReader::new),
+            mask: mask_buf
+                .map(Cursor::new)
+                .map(|c| MaskReader::new(c, seqlen)),
+            seq: seq
+                .map(Cursor::new)
+                .map(|c| SequenceReader::new(c, header.sequence_type())),
+            qual: qual
+                .map(Cursor::new)
+                .map(|c| SequenceReader::new(c, SequenceType::Text)),
+            header,
+            n: 0,
+            unit: MaskUnit::Unmasked(0),
+        }
+    }
+
+    /// Get the header extracted from the archive.
+    #[inline]
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Get the type of sequence in the archive being decoded.
+    #[inline]
+    pub fn sequence_type(&self) -> SequenceType {
+        self.header().sequence_type()
+    }
+
+    /// Get the index of the next record to be read from the archive.
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.n as u64
+    }
+
+    fn next_record(&mut self) -> Result<Record<'static>, Error> {
+        let id = self
+            .ids
+            .as_mut()
+            .and_then(|r| r.next())
+            .transpose()?
+            .map(|id| {
+                id.into_string()
+                    .map(std::borrow::Cow::Owned)
+                    .map_err(|e| Error::Utf8(e.utf8_error()))
+            })
+            .transpose()?;
+        let comment = self
+            .com
+            .as_mut()
+            .and_then(|r| r.next())
+            .transpose()?
+            .map(|com| {
+                com.into_string()
+                    .map(std::borrow::Cow::Owned)
+                    .map_err(|e| Error::Utf8(e.utf8_error()))
+            })
+            .transpose()?;
+        let length = self.len.as_mut().and_then(|r| r.next()).transpose()?;
+
+        let mut sequence = None;
+        let mut quality = None;
+        if let Some(l) = length {
+            sequence = self
+                .seq
+                .as_mut()
+                .map(|r| r.next(l))
+                .transpose()?
+                .map(std::borrow::Cow::Owned);
+            quality = self
+                .qual
+                .as_mut()
+                .map(|r| r.next(l))
+                .transpose()?
+                .map(std::borrow::Cow::Owned);
+            if let Some(seq) = sequence.as_mut() {
+                self.mask_sequence(seq.to_mut())?;
+            }
+        }
+
+        self.n += 1;
+        Ok(Record {
+            id,
+            comment,
+            sequence,
+            quality,
+            length,
+            // interval masking (`DecoderBuilder::mask_intervals`) is only
+            // implemented for the seekable `Decoder` for now
+            mask: None,
+        })
+    }
+
+    fn mask_sequence(&mut self, sequence: &mut str) -> Result<(), Error> {
+        let mut mask = self.unit.clone();
+        let mut seq = sequence;
+
+        if let Some(mask_reader) = self.mask.as_mut() {
+            loop {
+                match mask {
+                    MaskUnit::Masked(n) => {
+                        if n < seq.len() as u64 {
+                            seq[..n as usize].make_ascii_lowercase();
+                            seq = &mut seq[n as usize..];
+                        } else {
+                            self.unit = MaskUnit::Masked(n - seq.len() as u64);
+                            break;
+                        }
+                    }
+                    MaskUnit::Unmasked(n) => {
+                        if n < seq.len() as u64 {
+                            seq = &mut seq[n as usize..];
+                        } else {
+                            self.unit = MaskUnit::Unmasked(n - seq.len() as u64);
+                            break;
+                        }
+                    }
+                }
+                mask = match mask_reader.next() {
+                    Some(Ok(x)) => x,
+                    Some(Err(e)) => return Err(Error::Io(e)),
+                    None => {
+                        return Err(Error::Io(std::io::Error::new(
+                            std::io::ErrorKind::UnexpectedEof,
+                            "failed to get mask unit",
+                        )))
+                    }
+                };
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Iterator for StreamDecoder {
+    type Item = Result<Record<'static>, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.n as u64 >= self.header.number_of_sequences() {
+            return None;
+        }
+        Some(self.next_record())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.header.number_of_sequences() as usize - self.n;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for StreamDecoder {}
+
+impl FusedIterator for StreamDecoder {}
+
+impl DecoderBuilder {
+    /// Consume the builder to get a stream decoder reading data from `reader`.
+    ///
+    /// Unlike [`DecoderBuilder::with_reader`], this does not require `R`
+    /// to implement [`Seek`](std::io::Seek): every content block is
+    /// decompressed fully into memory, in on-disk order, before any
+    /// record is produced. Use this to decode a NAF archive arriving on
+    /// a pipe or a socket, at the cost of buffering the whole archive in
+    /// its decompressed form.
+    ///
+    /// `reader` only needs to implement [`Read`], not [`BufRead`]: this
+    /// wraps it in a [`BufReader`] itself, so a raw [`TcpStream`](std::net::TcpStream)
+    /// or [`Stdin`](std::io::Stdin) can be passed directly instead of
+    /// requiring the caller to pre-buffer it.
+    pub fn with_stream<R: Read>(&self, reader: R) -> Result<StreamDecoder, Error> {
+        let mut reader = BufReader::new(reader);
+        let buffer = reader.fill_buf()?;
+        let header = match super::parser::header(buffer) {
+            Ok((i, header)) => {
+                let consumed = buffer.len() - i.len();
+                reader.consume(consumed);
+                header
+            }
+            Err(e @ nom::Err::Incomplete(_)) => {
+                return Err(Error::from(e));
+            }
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                return Err(Error::from(e));
+            }
+        };
+
+        if header.flags().test(Flag::Title) {
+            let buf = reader.fill_buf()?;
+            let (i, _title) = super::parser::title(buf)?;
+            let consumed = buf.len() - i.len();
+            reader.consume(consumed);
+        }
+
+        let flags = header.flags();
+        macro_rules! read_block {
+            ($flag:ident, $use_block:expr) => {{
+                if flags.test(Flag::$flag) {
+                    let buf = reader.fill_buf()?;
+                    let (i, original_size) = super::parser::variable_u64(buf)?;
+                    let (i, compressed_size) = super::parser::variable_u64(i)?;
+                    let consumed = buf.len() - i.len();
+                    reader.consume(consumed);
+                    let mut compressed = vec![0u8; compressed_size as usize];
+                    reader.read_exact(&mut compressed)?;
+                    let data = read_block(&compressed, original_size)?;
+                    if $use_block {
+                        (Some(data), original_size)
+                    } else {
+                        // the compressed bytes were already read above,
+                        // which keeps the forward-only cursor in sync;
+                        // the decompressed data is dropped
+                        (None, original_size)
+                    }
+                } else {
+                    (None, 0)
+                }
+            }};
+        }
+
+        let (ids, _) = read_block!(Id, self.id);
+        let (com, _) = read_block!(Comment, self.comment);
+        let (len, _) = read_block!(Length, true);
+        let (mask_buf, _) = read_block!(Mask, self.mask);
+        let (seq, seqlen) = read_block!(Sequence, self.sequence);
+        let (qual, _) = read_block!(Quality, self.quality);
+
+        Ok(StreamDecoder::from_blocks(
+            header, ids, com, len, mask_buf, seq, seqlen, qual,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Decoder;
+
+    const ARCHIVE: &[u8] = include_bytes!("../../../data/LuxC.naf");
+
+    #[test]
+    fn with_stream_round_trip() {
+        // `LuxC.naf` has an id, a length and a sequence block, i.e. at
+        // least three content blocks in a row: decoding it with
+        // `with_stream` exercises the block-boundary desync regression
+        // (see `read_block`) that a single-block archive would hide.
+        let expected = Decoder::new(std::io::Cursor::new(ARCHIVE))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        let actual = StreamDecoder::new(ARCHIVE)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,76 @@
+//! Pluggable block compression backend.
+//!
+//! Mirrors [`crate::decoder::codec`] on the encode side: every content
+//! block is compressed through [`BlockCompressor`], the single point where
+//! the rest of `encoder` depends on a specific compression library.
+//! [`EncoderBuilder::new_buffer`](super::EncoderBuilder)/`new_dict_buffer`
+//! build an [`ActiveCompressor`], and the [`Encoder`](super::Encoder)
+//! fields only ever name that type, never `zstd::Encoder` directly.
+//!
+//! Unlike the decoder side, there is only one [`BlockCompressor`]
+//! implementation today: `ruzstd` (the pure-Rust backend already used to
+//! *decode* blocks when the `ruzstd` feature is enabled, see
+//! [`crate::decoder::codec`]) only implements a Zstandard decoder, not an
+//! encoder, so there is no pure-Rust path to switch to yet. This trait
+//! exists so that gap can be closed later by adding a second impl and
+//! widening [`ActiveCompressor`]'s selection, without touching `Encoder`
+//! or `EncoderBuilder` again; until then, `wasm32-unknown-unknown` and
+//! other C-toolchain-less targets remain out of reach for encoding, same
+//! as they were before this abstraction.
+
+use std::io::Error as IoError;
+use std::io::Write;
+
+/// A pluggable block compression backend.
+pub(super) trait BlockCompressor<B: Write>: Write + Sized {
+    /// Wrap `buffer` in a compressor at the given `level`.
+    fn new(buffer: B, level: i32) -> Result<Self, IoError>;
+
+    /// Wrap `buffer` in a compressor at the given `level`, seeded with `dictionary`.
+    fn with_dictionary(buffer: B, level: i32, dictionary: &[u8]) -> Result<Self, IoError>;
+
+    /// Finish the compressed stream and return the underlying buffer.
+    fn finish(self) -> Result<B, IoError>;
+}
+
+/// The only backend available today, using the C-backed `zstd` crate.
+///
+/// NAF content blocks are raw Zstandard frames without the 4-byte magic
+/// number (to save a few bytes per block), so magic bytes are always
+/// disabled here, to keep archives byte-for-byte compatible with other
+/// NAF implementations regardless of backend.
+pub(super) struct ZstdCompressor<'z, B: Write>(zstd::Encoder<'z, B>);
+
+impl<'z, B: Write> BlockCompressor<B> for ZstdCompressor<'z, B> {
+    fn new(buffer: B, level: i32) -> Result<Self, IoError> {
+        let mut encoder = zstd::Encoder::new(buffer, level)?;
+        encoder.include_magicbytes(false)?;
+        Ok(Self(encoder))
+    }
+
+    fn with_dictionary(buffer: B, level: i32, dictionary: &[u8]) -> Result<Self, IoError> {
+        let mut encoder = zstd::Encoder::with_dictionary(buffer, level, dictionary)?;
+        encoder.include_magicbytes(false)?;
+        Ok(Self(encoder))
+    }
+
+    fn finish(self) -> Result<B, IoError> {
+        self.0.finish()
+    }
+}
+
+impl<B: Write> Write for ZstdCompressor<'_, B> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, IoError> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), IoError> {
+        self.0.flush()
+    }
+}
+
+/// The [`BlockCompressor`] selected at build time.
+///
+/// Always [`ZstdCompressor`] today: see the module documentation for why
+/// the `ruzstd` feature does not affect this side yet.
+pub(super) type ActiveCompressor<'z, B> = ZstdCompressor<'z, B>;
@@ -1,6 +1,8 @@
+use std::borrow::Cow;
 use std::io::Error as IoError;
 use std::io::Write;
 
+mod compressor;
 mod counter;
 mod storage;
 mod writer;
@@ -8,6 +10,8 @@ mod writer;
 pub use self::storage::Memory;
 pub use self::storage::Storage;
 
+use self::compressor::ActiveCompressor;
+use self::compressor::BlockCompressor;
 use self::counter::WriteCounter;
 use self::writer::SequenceWriter;
 use crate::error::Error;
@@ -17,6 +21,8 @@ use crate::data::Flags;
 use crate::data::Header;
 use crate::data::Record;
 use crate::data::SequenceType;
+use crate::extension::ExtensionBlock;
+use crate::extension::ExtensionField;
 use crate::FormatVersion;
 
 fn write_variable_length<W: Write>(mut n: u64, mut w: W) -> Result<(), IoError> {
@@ -43,6 +49,35 @@ fn write_length<W: Write>(mut l: u64, mut w: W) -> Result<(), IoError> {
     w.write_all(&n.to_le_bytes()[..])
 }
 
+/// Write a single mask block run length.
+///
+/// The mask block uses its own encoding for run lengths, distinct from
+/// [`write_variable_length`]: full `0xFF` bytes each contribute `0xFF` to
+/// the run, terminated by a byte strictly less than `0xFF`. This mirrors
+/// how `MaskReader` (the decoder-side counterpart) sums them back up, so a
+/// run longer than `0xFF` is simply split across several bytes instead of
+/// overflowing a single one.
+fn write_mask_length<W: Write>(mut n: u64, mut w: W) -> Result<(), IoError> {
+    while n >= 0xFF {
+        w.write_all(&[0xFF])?;
+        n -= 0xFF;
+    }
+    w.write_all(&[n as u8])
+}
+
+/// Write `s` followed by its NUL terminator in a single `write_all` call.
+///
+/// The id and comment blocks are NUL-terminated strings; staging the
+/// string and its terminator in one buffer instead of issuing two
+/// separate `write_all` calls halves the number of writes [`Encoder::push`]
+/// performs per record for these two fields.
+fn write_terminated<W: Write>(s: &str, mut w: W) -> Result<(), IoError> {
+    let mut buf = Vec::with_capacity(s.len() + 1);
+    buf.extend_from_slice(s.as_bytes());
+    buf.push(0);
+    w.write_all(&buf)
+}
+
 /// A builder to configure and initialize an [`Encoder`].
 ///
 /// The fields to encode are *opt-in*: only the fields enabled through the
@@ -66,7 +101,13 @@ pub struct EncoderBuilder {
     sequence: bool,
     quality: bool,
     comment: bool,
+    mask: bool,
     compression_level: i32,
+    dictionary: Option<Vec<u8>>,
+    extensions: ExtensionBlock,
+    reserve_hint: Option<(usize, usize)>,
+    #[cfg(feature = "crypto")]
+    encryption: Option<crate::crypto::EncryptionContext>,
 }
 
 impl EncoderBuilder {
@@ -77,8 +118,14 @@ impl EncoderBuilder {
             id: false,
             quality: false,
             comment: false,
+            mask: false,
+            extensions: ExtensionBlock::new(),
             sequence: false,
             compression_level: 0,
+            dictionary: None,
+            reserve_hint: None,
+            #[cfg(feature = "crypto")]
+            encryption: None,
         }
     }
 
@@ -101,6 +148,7 @@ impl EncoderBuilder {
         builder.quality(flags.test(Flag::Quality));
         builder.sequence(flags.test(Flag::Sequence));
         builder.comment(flags.test(Flag::Comment));
+        builder.mask(flags.test(Flag::Mask));
         builder
     }
 
@@ -132,6 +180,21 @@ impl EncoderBuilder {
         self
     }
 
+    /// Whether or not to encode soft-masked (lowercase) regions of the sequence.
+    ///
+    /// When enabled, each sequence is scanned for runs of lowercase
+    /// letters, which are run-length encoded into a dedicated mask content
+    /// block instead of being stored as part of the sequence itself: the
+    /// sequence is upper-cased before being handed to the sequence writer,
+    /// so the 4-bit nucleotide encoding stays canonical, and the case is
+    /// restored on the way back out by
+    /// [`DecoderBuilder::mask`](crate::DecoderBuilder::mask).
+    #[inline]
+    pub fn mask(&mut self, mask: bool) -> &mut Self {
+        self.mask = mask;
+        self
+    }
+
     /// The compression level to use for `zstd` compression.
     ///
     /// Pass `0` to use the default `zstd` value, otherwise any
@@ -143,14 +206,122 @@ impl EncoderBuilder {
         self
     }
 
+    /// Use a precomputed Zstandard dictionary to compress content blocks.
+    ///
+    /// Applies to the identifier, comment and sequence blocks, which
+    /// benefit the most from sharing entropy tables across many short
+    /// records. The same dictionary must be passed to
+    /// [`DecoderBuilder::dictionary`](crate::DecoderBuilder::dictionary)
+    /// to read the archive back, as it is not stored within it. Use
+    /// [`train_dictionary`] to derive one from a sample of records.
+    pub fn dictionary(&mut self, dictionary: impl Into<Vec<u8>>) -> &mut Self {
+        self.dictionary = Some(dictionary.into());
+        self
+    }
+
+    /// Pre-allocate in-memory block buffers to roughly fit `records` records averaging `avg_len` bytes each.
+    ///
+    /// This is only a hint, and only acted on by in-memory storage (see
+    /// [`Memory`]/[`EncoderBuilder::with_memory`]):
+    /// [`Storage::create_buffer_with_capacity`] reserves `records * avg_len`
+    /// bytes upfront for every content block buffer, trading a larger
+    /// initial allocation for fewer reallocations as [`Encoder::push`] is
+    /// called repeatedly, which matters most when pushing a large, roughly
+    /// known number of short records. Backends that do not buffer in
+    /// memory (e.g. the `tempfile` feature) ignore it entirely. Pass `0`
+    /// for either argument to disable the hint again.
+    #[inline]
+    pub fn reserve_hint(&mut self, records: usize, avg_len: usize) -> &mut Self {
+        self.reserve_hint = Some((records, avg_len));
+        self
+    }
+
+    /// Set the archive creation timestamp, in Unix epoch seconds.
+    ///
+    /// Stored in the trailing extension block (see [`Flag::Extended`]);
+    /// has no effect on decoders older than this feature, which simply
+    /// never see the bit set.
+    pub fn created_at(&mut self, created_at: u64) -> &mut Self {
+        self.extensions.push(ExtensionField::CreatedAt(created_at));
+        self
+    }
+
+    /// Set the name of the program that produced the archive.
+    pub fn producer(&mut self, producer: impl Into<String>) -> &mut Self {
+        self.extensions.push(ExtensionField::Producer(producer.into()));
+        self
+    }
+
+    /// Set the name of the file the archive was produced from.
+    pub fn source_filename(&mut self, source_filename: impl Into<String>) -> &mut Self {
+        self.extensions
+            .push(ExtensionField::SourceFilename(source_filename.into()));
+        self
+    }
+
+    /// Add a free-form UTF-8 annotation to the archive.
+    ///
+    /// Can be called several times; every annotation is kept, in order.
+    pub fn annotation(&mut self, annotation: impl Into<String>) -> &mut Self {
+        self.extensions
+            .push(ExtensionField::Annotation(annotation.into()));
+        self
+    }
+
+    /// Encrypt content blocks for `recipient_public_key`, an X25519 public key.
+    ///
+    /// Borrows the layered design of the MLA archive format: content
+    /// blocks are still compressed exactly as before, but the compressed
+    /// bytes are additionally encrypted with AES-256-CTR and authenticated
+    /// with HMAC-SHA256, each keyed by its own HKDF-SHA256-expanded key
+    /// derived from a shared secret obtained through X25519 Diffie-Hellman
+    /// between a fresh, archive-specific ephemeral keypair and
+    /// `recipient_public_key`. The ephemeral public key is stored alongside
+    /// the archive (see [`Flag::Extended`]) so the recipient can re-derive
+    /// the same keys from their private key with
+    /// [`DecoderBuilder::private_key`](crate::DecoderBuilder::private_key);
+    /// it does not by itself let anyone else derive them.
+    ///
+    /// Requires the `crypto` feature, which pulls in `x25519-dalek`, `aes`,
+    /// `ctr`, `hkdf` and `hmac`; the default build depends on none of them.
+    ///
+    /// Reading an encrypted archive back is not implemented yet (see
+    /// [`crate::crypto`]), so setting this currently makes
+    /// [`EncoderBuilder::with_storage`]/[`EncoderBuilder::with_memory`] fail
+    /// with [`Error::Encrypted`] instead of silently producing an archive
+    /// that nothing, not even this crate, can decode.
+    #[cfg(feature = "crypto")]
+    pub fn recipient_public_key(&mut self, recipient_public_key: [u8; 32]) -> &mut Self {
+        self.encryption = Some(crate::crypto::EncryptionContext::new(&recipient_public_key));
+        self
+    }
+
+    /// The capacity hint to pass to [`Storage::create_buffer_with_capacity`], from [`EncoderBuilder::reserve_hint`].
+    fn capacity_hint(&self) -> usize {
+        self.reserve_hint
+            .map(|(records, avg_len)| records.saturating_mul(avg_len))
+            .unwrap_or(0)
+    }
+
     /// Create a new compressed writer using a storage buffer.
     fn new_buffer<'z, S: Storage>(
         &self,
         storage: &S,
-    ) -> Result<zstd::Encoder<'z, S::Buffer>, IoError> {
-        let mut buffer = zstd::Encoder::new(storage.create_buffer()?, self.compression_level)?;
-        buffer.include_magicbytes(false)?;
-        Ok(buffer)
+    ) -> Result<ActiveCompressor<'z, S::Buffer>, IoError> {
+        let buffer = storage.create_buffer_with_capacity(self.capacity_hint())?;
+        ActiveCompressor::new(buffer, self.compression_level)
+    }
+
+    /// Create a new compressed writer, loading the builder's dictionary if set.
+    fn new_dict_buffer<'z, S: Storage>(
+        &self,
+        storage: &S,
+    ) -> Result<ActiveCompressor<'z, S::Buffer>, IoError> {
+        let buffer = storage.create_buffer_with_capacity(self.capacity_hint())?;
+        match &self.dictionary {
+            Some(dict) => ActiveCompressor::with_dictionary(buffer, self.compression_level, dict),
+            None => ActiveCompressor::new(buffer, self.compression_level),
+        }
     }
 
     /// Consume the builder to get an encoder using in-memory storage.
@@ -161,6 +332,17 @@ impl EncoderBuilder {
 
     /// Consume the builder to get an encoder using the given storage.
     pub fn with_storage<'z, S: Storage>(&self, storage: S) -> Result<Encoder<'z, S>, Error> {
+        // Reading an encrypted archive back is not wired up yet (see
+        // `crate::crypto`); refuse to write one at all rather than produce
+        // a write-only archive that nothing, including this crate, can
+        // decode.
+        #[cfg(feature = "crypto")]
+        if self.encryption.is_some() {
+            return Err(Error::Encrypted(
+                "decoding encrypted archives is not supported yet, refusing to write one that could never be read back",
+            ));
+        }
+
         let mut header = Header::default();
 
         header.sequence_type = self.sequence_type;
@@ -180,26 +362,49 @@ impl EncoderBuilder {
             header.flags.set(Flag::Sequence);
             header.flags.set(Flag::Length);
         }
+        if self.mask {
+            header.flags.set(Flag::Mask);
+        }
         if self.quality {
             header.flags.set(Flag::Quality);
             header.flags.set(Flag::Length);
         }
 
+        #[allow(unused_mut)]
+        let mut extensions = self.extensions.clone();
+        #[cfg(feature = "crypto")]
+        if let Some(encryption) = &self.encryption {
+            extensions.push(ExtensionField::Encryption {
+                ephemeral_public_key: encryption.ephemeral_public_key(),
+            });
+        }
+
+        let extensions = if extensions.is_empty() {
+            None
+        } else {
+            header.flags.set(Flag::Extended);
+            let mut payload = Vec::new();
+            extensions.write(&mut payload)?;
+            let mut compressor = ActiveCompressor::new(Vec::new(), self.compression_level)?;
+            compressor.write_all(&payload)?;
+            Some((payload.len() as u64, compressor.finish()?))
+        };
+
         let lens = self.new_buffer(&storage)?;
         let id = if self.id {
-            Some(WriteCounter::new(self.new_buffer(&storage)?))
+            Some(WriteCounter::new(self.new_dict_buffer(&storage)?))
         } else {
             None
         };
         let com = if self.comment {
-            Some(WriteCounter::new(self.new_buffer(&storage)?))
+            Some(WriteCounter::new(self.new_dict_buffer(&storage)?))
         } else {
             None
         };
         let seq = if self.sequence {
             Some(WriteCounter::new(SequenceWriter::new(
                 self.sequence_type,
-                self.new_buffer(&storage)?,
+                self.new_dict_buffer(&storage)?,
             )))
         } else {
             None
@@ -209,6 +414,11 @@ impl EncoderBuilder {
         } else {
             None
         };
+        let mask = if self.mask {
+            Some(WriteCounter::new(self.new_buffer(&storage)?))
+        } else {
+            None
+        };
 
         Ok(Encoder {
             header,
@@ -217,11 +427,60 @@ impl EncoderBuilder {
             qual,
             com,
             id,
+            mask,
+            mask_run: (false, 0),
             len: WriteCounter::new(lens),
+            extensions,
+            #[cfg(feature = "crypto")]
+            encryption_key: self
+                .encryption
+                .as_ref()
+                .map(|encryption| (encryption.enc_key(), encryption.mac_key())),
         })
     }
 }
 
+/// Either the final output writer itself, or an encrypting layer around it.
+///
+/// Used by [`Encoder::write`] so the same `write_block!` invocations work
+/// whether or not [`EncoderBuilder::recipient_public_key`] was set, without
+/// threading an `Option` through every call.
+enum Sink<'a, W> {
+    Plain(&'a mut W),
+    #[cfg(feature = "crypto")]
+    Encrypted(crate::crypto::EncryptionLayer<&'a mut W>),
+}
+
+impl<W: Write> Write for Sink<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, IoError> {
+        match self {
+            Sink::Plain(w) => w.write(buf),
+            #[cfg(feature = "crypto")]
+            Sink::Encrypted(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), IoError> {
+        match self {
+            Sink::Plain(w) => w.flush(),
+            #[cfg(feature = "crypto")]
+            Sink::Encrypted(w) => w.flush(),
+        }
+    }
+}
+
+/// Train a Zstandard dictionary from a sample of records.
+///
+/// `samples` should contain representative fragments of the data that will
+/// be compressed, e.g. the raw identifier, comment or sequence strings of a
+/// subset of records; `max_size` bounds the size of the resulting
+/// dictionary in bytes. The result can be passed to both
+/// [`EncoderBuilder::dictionary`] and
+/// [`DecoderBuilder::dictionary`](crate::DecoderBuilder::dictionary).
+pub fn train_dictionary(samples: &[Vec<u8>], max_size: usize) -> Result<Vec<u8>, Error> {
+    zstd::dict::from_samples(samples, max_size).map_err(Error::from)
+}
+
 /// An encoder for Nucleotide Archive Format files.
 ///
 /// NAF archives decomposes data into separate content blocks, which means
@@ -232,12 +491,30 @@ impl EncoderBuilder {
 pub struct Encoder<'z, S: Storage> {
     header: Header,
     storage: S,
-    id: Option<WriteCounter<zstd::Encoder<'z, S::Buffer>>>,
-    len: WriteCounter<zstd::Encoder<'z, S::Buffer>>,
-    com: Option<WriteCounter<zstd::Encoder<'z, S::Buffer>>>,
-    seq: Option<WriteCounter<SequenceWriter<zstd::Encoder<'z, S::Buffer>>>>,
-    qual: Option<WriteCounter<zstd::Encoder<'z, S::Buffer>>>,
-    // mask: WriteCounter<zstd::Encoder<'z, S::Buffer>>,
+    id: Option<WriteCounter<ActiveCompressor<'z, S::Buffer>>>,
+    len: WriteCounter<ActiveCompressor<'z, S::Buffer>>,
+    com: Option<WriteCounter<ActiveCompressor<'z, S::Buffer>>>,
+    seq: Option<WriteCounter<SequenceWriter<ActiveCompressor<'z, S::Buffer>>>>,
+    qual: Option<WriteCounter<ActiveCompressor<'z, S::Buffer>>>,
+    mask: Option<WriteCounter<ActiveCompressor<'z, S::Buffer>>>,
+    /// The currently open mask run, as `(masked, length)`.
+    ///
+    /// The mask block is one continuous alternating run sequence spanning
+    /// every pushed record (mirroring how the decoder's `MaskReader` walks
+    /// it back), so a run may start in one record and finish in the next;
+    /// this keeps the open run across calls to [`Encoder::push`], only
+    /// flushing it to the mask buffer once a case change breaks it.
+    mask_run: (bool, u64),
+    /// The already-compressed extension block, as `(original_size, compressed_bytes)`.
+    ///
+    /// Unlike the other blocks, this does not grow with `push`: every
+    /// field was set on the builder before the encoder was even created,
+    /// so it is compressed once, up front, instead of through a
+    /// [`WriteCounter`] that accumulates writes across records.
+    extensions: Option<(u64, Vec<u8>)>,
+    /// The `(enc_key, mac_key)` pair derived from [`EncoderBuilder::recipient_public_key`], if any.
+    #[cfg(feature = "crypto")]
+    encryption_key: Option<([u8; 32], [u8; 32])>,
 }
 
 impl<S: Storage> Encoder<'_, S> {
@@ -246,7 +523,11 @@ impl<S: Storage> Encoder<'_, S> {
     /// The records contents are written to the temporary storage used
     /// internally by the [`Encoder`], but the [`Encoder::write`] method
     /// needs to be called once all records have been added to build the
-    /// final archive.
+    /// final archive. Content blocks are append-only buffers that are not
+    /// flushed between records: flushing forces a new compression frame
+    /// boundary, so deferring it to [`Encoder::write`] keeps the
+    /// per-record overhead of `push` low and lets `zstd` find redundancy
+    /// across records instead of just within one.
     pub fn push(&mut self, record: &Record) -> Result<(), Error> {
         let mut written_length = None;
 
@@ -257,8 +538,7 @@ impl<S: Storage> Encoder<'_, S> {
 
         if let Some(id_writer) = self.id.as_mut() {
             if let Some(id) = record.id.as_ref() {
-                id_writer.write_all(id.as_bytes())?;
-                id_writer.write_all(b"\0")?;
+                write_terminated(id, id_writer)?;
             } else {
                 return Err(Error::MissingField("id"));
             }
@@ -266,14 +546,19 @@ impl<S: Storage> Encoder<'_, S> {
 
         if let Some(com_writer) = self.com.as_mut() {
             if let Some(com) = record.comment.as_ref() {
-                com_writer.write_all(com.as_bytes())?;
-                com_writer.write_all(b"\0")?;
-                com_writer.flush()?;
+                write_terminated(com, com_writer)?;
             } else {
                 return Err(Error::MissingField("comment"));
             }
         }
 
+        if self.mask.is_some() {
+            match record.sequence.as_ref() {
+                Some(seq) => self.push_mask_runs(seq.as_bytes())?,
+                None => return Err(Error::MissingField("sequence")),
+            }
+        }
+
         if let Some(seq_writer) = self.seq.as_mut() {
             if let Some(seq) = record.sequence.as_ref() {
                 match written_length {
@@ -288,14 +573,22 @@ impl<S: Storage> Encoder<'_, S> {
                         written_length = Some(length as u64);
                     }
                 }
-                if let Err(e) = seq_writer.write(seq.as_bytes()) {
+                // soft-masked regions are stored as lowercase in `Record`,
+                // but the 4-bit nucleotide encoding only recognizes
+                // uppercase letters, so the mask needs to be peeled off
+                // into the mask block before the sequence is written here
+                let canonical = if self.mask.is_some() {
+                    Cow::Owned(seq.to_uppercase())
+                } else {
+                    Cow::Borrowed(seq.as_ref())
+                };
+                if let Err(e) = seq_writer.write(canonical.as_bytes()) {
                     if e.kind() == std::io::ErrorKind::InvalidData {
                         return Err(Error::InvalidSequence);
                     } else {
                         return Err(Error::Io(e));
                     }
                 }
-                seq_writer.flush()?;
             } else {
                 return Err(Error::MissingField("sequence"));
             }
@@ -316,7 +609,6 @@ impl<S: Storage> Encoder<'_, S> {
                     }
                 }
                 qual_writer.write_all(qual.as_bytes())?;
-                qual_writer.flush()?;
             } else {
                 return Err(Error::MissingField("quality"));
             }
@@ -326,12 +618,79 @@ impl<S: Storage> Encoder<'_, S> {
         Ok(())
     }
 
+    /// Push every [`Record`] deserialized from a `serde` sequence.
+    ///
+    /// `deserializer` is expected to produce a sequence of record maps
+    /// (for instance a JSON array of objects when reading with
+    /// `serde_json`). Each element is deserialized one at a time and
+    /// immediately forwarded to [`Encoder::push`], so only a single
+    /// record is held in memory regardless of how many the input
+    /// contains; fields the encoder was not configured to store (see
+    /// [`EncoderBuilder`]) are ignored exactly as they would be for a
+    /// hand-built [`Record`]. Borrowed string fields are deserialized
+    /// without copying whenever the deserializer supports it, same as
+    /// deserializing a [`Record`] directly.
+    #[cfg(feature = "serde")]
+    pub fn push_serde<'de, D>(&mut self, deserializer: D) -> Result<(), Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct RecordSeqVisitor<'a, 'z, S: Storage>(&'a mut Encoder<'z, S>);
+
+        impl<'de, 'a, 'z, S: Storage> serde::de::Visitor<'de> for RecordSeqVisitor<'a, 'z, S> {
+            type Value = ();
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a sequence of NAF records")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                while let Some(record) = seq.next_element::<Record<'de>>()? {
+                    self.0.push(&record).map_err(serde::de::Error::custom)?;
+                }
+                Ok(())
+            }
+        }
+
+        deserializer
+            .deserialize_seq(RecordSeqVisitor(self))
+            .map_err(|e| Error::Serde(e.to_string()))
+    }
+
+    /// Extend the open mask run with `sequence`'s case, flushing completed
+    /// runs to the mask buffer as they are broken by a case change.
+    fn push_mask_runs(&mut self, sequence: &[u8]) -> Result<(), Error> {
+        let (mut masked, mut run) = self.mask_run;
+        for &byte in sequence {
+            let is_masked = byte.is_ascii_lowercase();
+            if is_masked == masked {
+                run += 1;
+            } else {
+                write_mask_length(run, self.mask.as_mut().unwrap())?;
+                masked = is_masked;
+                run = 1;
+            }
+        }
+        self.mask_run = (masked, run);
+        Ok(())
+    }
+
     /// Finalize the archive and write it to the given writer.
     ///
     /// This method consumes the [`Encoder`], since it cannot receive any
     /// additional [`Record`] after the compressed blocks have been
     /// finalized.
-    pub fn write<W: Write>(self, mut file: W) -> Result<(), Error> {
+    pub fn write<W: Write>(mut self, mut file: W) -> Result<(), Error> {
+        // flush the final open mask run, which `push` leaves pending since
+        // it cannot know a record will be the last one to touch it
+        if let Some(mask_writer) = self.mask.as_mut() {
+            let (_, run) = self.mask_run;
+            write_mask_length(run, mask_writer)?;
+        }
+
         // --- header ---
         file.write_all(&[0x01, 0xF9, 0xEC])?; // format descriptor
 
@@ -355,6 +714,16 @@ impl<S: Storage> Encoder<'_, S> {
 
         // -- ids ---
 
+        #[cfg(feature = "crypto")]
+        let mut sink = match &self.encryption_key {
+            Some((enc_key, mac_key)) => {
+                Sink::Encrypted(crate::crypto::EncryptionLayer::new(&mut file, enc_key, mac_key)?)
+            }
+            None => Sink::Plain(&mut file),
+        };
+        #[cfg(not(feature = "crypto"))]
+        let mut sink = Sink::Plain(&mut file);
+
         macro_rules! write_block {
             ($field:expr) => {
                 write_block!($field, |x| Result::<_, Error>::Ok(x))
@@ -366,9 +735,9 @@ impl<S: Storage> Encoder<'_, S> {
                     buffer.flush()?;
                     let compressed_length = self.storage.buffer_length(&buffer)?;
 
-                    write_variable_length(uncompressed_length, &mut file)?;
-                    write_variable_length(compressed_length as u64, &mut file)?;
-                    self.storage.write_buffer(buffer, &mut file)?;
+                    write_variable_length(uncompressed_length, &mut sink)?;
+                    write_variable_length(compressed_length as u64, &mut sink)?;
+                    self.storage.write_buffer(buffer, &mut sink)?;
                 }
             };
         }
@@ -376,9 +745,25 @@ impl<S: Storage> Encoder<'_, S> {
         write_block!(self.id);
         write_block!(self.com);
         write_block!(Some(self.len));
+        write_block!(self.mask);
         write_block!(self.seq, |f: SequenceWriter<_>| f.into_inner());
         write_block!(self.qual);
 
+        #[cfg(feature = "crypto")]
+        if let Sink::Encrypted(encrypted) = sink {
+            encrypted.finish()?;
+        }
+        #[cfg(not(feature = "crypto"))]
+        drop(sink);
+
+        // --- extensions ---
+
+        if let Some((original_size, compressed)) = self.extensions {
+            write_variable_length(original_size, &mut file)?;
+            write_variable_length(compressed.len() as u64, &mut file)?;
+            file.write_all(&compressed)?;
+        }
+
         file.flush()?;
         Ok(())
     }
@@ -439,6 +824,73 @@ mod tests {
         encoder.write(f).unwrap();
     }
 
+    #[test]
+    fn encoder_mask() {
+        let mut encoder = EncoderBuilder::new(SequenceType::Dna)
+            .id(true)
+            .sequence(true)
+            .mask(true)
+            .with_memory()
+            .unwrap();
+        let r1 = Record {
+            id: Some("r1".into()),
+            sequence: Some("ATTatcGC".into()),
+            ..Default::default()
+        };
+        encoder.push(&r1).unwrap();
+
+        let r2 = Record {
+            id: Some("r2".into()),
+            // starts lowercase, right after `r1` ended on uppercase letters
+            sequence: Some("aaGGCC".into()),
+            ..Default::default()
+        };
+        encoder.push(&r2).unwrap();
+
+        let mut buffer = Vec::new();
+        encoder.write(&mut buffer).unwrap();
+
+        let decoder = crate::DecoderBuilder::new()
+            .with_reader(std::io::Cursor::new(buffer))
+            .unwrap();
+        let records = decoder.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records[0].sequence.as_deref(), Some("ATTatcGC"));
+        assert_eq!(records[1].sequence.as_deref(), Some("aaGGCC"));
+    }
+
+    #[test]
+    fn encoder_reserve_hint() {
+        let mut encoder = EncoderBuilder::new(SequenceType::Dna)
+            .id(true)
+            .sequence(true)
+            .reserve_hint(2, 4)
+            .with_memory()
+            .unwrap();
+        let r1 = Record {
+            id: Some("r1".into()),
+            sequence: Some("ATGC".into()),
+            ..Default::default()
+        };
+        encoder.push(&r1).unwrap();
+
+        let r2 = Record {
+            id: Some("r2".into()),
+            sequence: Some("TTAA".into()),
+            ..Default::default()
+        };
+        encoder.push(&r2).unwrap();
+
+        let mut buffer = Vec::new();
+        encoder.write(&mut buffer).unwrap();
+
+        let decoder = crate::DecoderBuilder::new()
+            .with_reader(std::io::Cursor::new(buffer))
+            .unwrap();
+        let records = decoder.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records[0].sequence.as_deref(), Some("ATGC"));
+        assert_eq!(records[1].sequence.as_deref(), Some("TTAA"));
+    }
+
     #[cfg(feature = "tempfile")]
     #[test]
     fn encoder_tempfile() {
@@ -14,6 +14,15 @@ pub trait Storage: Sized {
     type Buffer: Write;
     /// Create a new buffer.
     fn create_buffer(&self) -> Result<Self::Buffer, IoError>;
+    /// Create a new buffer, pre-allocated to roughly hold `capacity` bytes.
+    ///
+    /// The default implementation ignores the hint and defers to
+    /// [`Storage::create_buffer`]; only storage that actually buffers in
+    /// memory (see [`Memory`]) can act on it to cut down on reallocations.
+    fn create_buffer_with_capacity(&self, capacity: usize) -> Result<Self::Buffer, IoError> {
+        let _ = capacity;
+        self.create_buffer()
+    }
     /// Write the contents of the buffer to the given writer.
     fn write_buffer<W: Write>(&self, buffer: Self::Buffer, file: &mut W) -> Result<(), IoError>;
     /// Get the total length of the buffer content.
@@ -53,6 +62,9 @@ impl Storage for Memory {
     fn create_buffer(&self) -> Result<Self::Buffer, IoError> {
         Ok(Vec::new())
     }
+    fn create_buffer_with_capacity(&self, capacity: usize) -> Result<Self::Buffer, IoError> {
+        Ok(Vec::with_capacity(capacity))
+    }
     fn buffer_length(&self, buffer: &Self::Buffer) -> Result<u64, IoError> {
         Ok(buffer.len() as u64)
     }
@@ -3,10 +3,77 @@ use std::io::Write;
 
 use crate::data::SequenceType;
 
+/// Sentinel stored in [`ENCODE_LUT_DNA`]/[`ENCODE_LUT_RNA`] for bytes that do
+/// not encode a valid nucleotide letter for that sequence type.
+#[cfg(feature = "lut")]
+const INVALID: u8 = 0xFF;
+
+/// Map one ASCII nucleotide letter to its 4-bit NAF code.
+///
+/// `pyrimidine` is `b'T'` for DNA or `b'U'` for RNA: the two share the same
+/// code (`0x01`) but only one is a valid letter for a given sequence type,
+/// so the other must still be rejected rather than silently accepted.
+#[cfg(feature = "lut")]
+const fn encode_byte(c: u8, pyrimidine: u8) -> u8 {
+    match c {
+        b'A' => 0x08,
+        b'C' => 0x04,
+        b'G' => 0x02,
+        b'R' => 0x0A,
+        b'Y' => 0x05,
+        b'S' => 0x06,
+        b'W' => 0x09,
+        b'K' => 0x03,
+        b'M' => 0x0C,
+        b'B' => 0x07,
+        b'D' => 0x0B,
+        b'H' => 0x0D,
+        b'V' => 0x0E,
+        b'N' => 0x0F,
+        b'-' => 0x00,
+        c if c == pyrimidine => 0x01,
+        _ => INVALID,
+    }
+}
+
+#[cfg(feature = "lut")]
+const fn build_encode_lut(pyrimidine: u8) -> [u8; 256] {
+    let mut table = [INVALID; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = encode_byte(i as u8, pyrimidine);
+        i += 1;
+    }
+    table
+}
+
+/// 256-entry encode table for DNA sequences (`T` is the pyrimidine letter).
+#[cfg(feature = "lut")]
+const ENCODE_LUT_DNA: [u8; 256] = build_encode_lut(b'T');
+
+/// 256-entry encode table for RNA sequences (`U` is the pyrimidine letter).
+#[cfg(feature = "lut")]
+const ENCODE_LUT_RNA: [u8; 256] = build_encode_lut(b'U');
+
 pub struct SequenceWriter<W: Write> {
     ty: SequenceType,
     writer: W,
     cache: Option<u8>,
+    /// The 256-entry table matching `ty`, picked once instead of branching
+    /// on `ty` for every letter encoded.
+    #[cfg(feature = "lut")]
+    encode_lut: &'static [u8; 256],
+    /// Whether the current CPU supports the SIMD encode path, probed once
+    /// instead of on every 16-byte batch.
+    #[cfg(feature = "simd")]
+    simd_capable: bool,
+    /// `_mm_shuffle_epi8` lookup vectors for the SIMD encode path, built
+    /// once from `ty` at construction. See `encode_simd_batch` for how
+    /// they are used.
+    #[cfg(all(target_arch = "x86_64", feature = "simd"))]
+    code_lookup: core::arch::x86_64::__m128i,
+    #[cfg(all(target_arch = "x86_64", feature = "simd"))]
+    canon_lookup: core::arch::x86_64::__m128i,
 }
 
 impl<W: Write> SequenceWriter<W> {
@@ -15,6 +82,31 @@ impl<W: Write> SequenceWriter<W> {
             writer,
             ty,
             cache: None,
+            #[cfg(feature = "lut")]
+            encode_lut: match ty {
+                SequenceType::Rna => &ENCODE_LUT_RNA,
+                _ => &ENCODE_LUT_DNA,
+            },
+            #[cfg(feature = "simd")]
+            simd_capable: Self::simd_capable(),
+            #[cfg(all(target_arch = "x86_64", feature = "simd"))]
+            code_lookup: Self::build_code_lookup(ty),
+            #[cfg(all(target_arch = "x86_64", feature = "simd"))]
+            canon_lookup: Self::build_canon_lookup(),
+        }
+    }
+
+    /// Probe, once, whether this process can use the SIMD encode path.
+    #[cfg(feature = "simd")]
+    #[inline]
+    fn simd_capable() -> bool {
+        #[cfg(target_arch = "x86_64")]
+        {
+            crate::decoder::reader::simd_supported()
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            false
         }
     }
 
@@ -29,28 +121,149 @@ impl<W: Write> SequenceWriter<W> {
 
     #[inline]
     fn encode(&self, c: u8) -> Result<u8, IoError> {
-        match c {
-            b'A' => Ok(0x08),
-            b'C' => Ok(0x04),
-            b'G' => Ok(0x02),
-            b'T' if self.ty == SequenceType::Dna => Ok(0x01),
-            b'U' if self.ty == SequenceType::Rna => Ok(0x01),
-            b'R' => Ok(0x0A),
-            b'Y' => Ok(0x05),
-            b'S' => Ok(0x06),
-            b'W' => Ok(0x09),
-            b'K' => Ok(0x03),
-            b'M' => Ok(0x0C),
-            b'B' => Ok(0x07),
-            b'D' => Ok(0x0B),
-            b'H' => Ok(0x0D),
-            b'V' => Ok(0x0E),
-            b'N' => Ok(0x0F),
-            b'-' => Ok(0x00),
-            _ => Err(IoError::new(
-                std::io::ErrorKind::InvalidData,
-                "unexpected sequence character",
-            )),
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "lut")] {
+                match self.encode_lut[c as usize] {
+                    INVALID => Err(IoError::new(
+                        std::io::ErrorKind::InvalidData,
+                        "unexpected sequence character",
+                    )),
+                    code => Ok(code),
+                }
+            } else {
+                match c {
+                    b'A' => Ok(0x08),
+                    b'C' => Ok(0x04),
+                    b'G' => Ok(0x02),
+                    b'T' if self.ty == SequenceType::Dna => Ok(0x01),
+                    b'U' if self.ty == SequenceType::Rna => Ok(0x01),
+                    b'R' => Ok(0x0A),
+                    b'Y' => Ok(0x05),
+                    b'S' => Ok(0x06),
+                    b'W' => Ok(0x09),
+                    b'K' => Ok(0x03),
+                    b'M' => Ok(0x0C),
+                    b'B' => Ok(0x07),
+                    b'D' => Ok(0x0B),
+                    b'H' => Ok(0x0D),
+                    b'V' => Ok(0x0E),
+                    b'N' => Ok(0x0F),
+                    b'-' => Ok(0x00),
+                    _ => Err(IoError::new(
+                        std::io::ErrorKind::InvalidData,
+                        "unexpected sequence character",
+                    )),
+                }
+            }
+        }
+    }
+
+    /// Build the `_mm_shuffle_epi8` table mapping `letter & 0x0F` to its
+    /// 4-bit NAF code, or `0xFF` for letters the fast path does not handle.
+    ///
+    /// `T` (0x54) and `U` (0x55) land in different slots (4 and 5
+    /// respectively) since their low bit is part of the low nibble, so
+    /// this table enables whichever one of the two slots
+    /// matches `ty` and leaves the other as `0xFF`; `build_canon_lookup`
+    /// below does not need to vary by `ty` at all.
+    #[cfg(all(target_arch = "x86_64", feature = "simd"))]
+    fn build_code_lookup(ty: SequenceType) -> core::arch::x86_64::__m128i {
+        use core::arch::x86_64::_mm_set_epi8;
+        let t_code = if ty == SequenceType::Dna { 0x01 } else { 0xFFu8 as i8 };
+        let u_code = if ty == SequenceType::Rna { 0x01 } else { 0xFFu8 as i8 };
+        unsafe {
+            _mm_set_epi8(
+                0xFFu8 as i8, // 0xF
+                0x0F,         // 0xE: N
+                0x00,         // 0xD: -
+                0xFFu8 as i8, // 0xC
+                0xFFu8 as i8, // 0xB
+                0xFFu8 as i8, // 0xA
+                0xFFu8 as i8, // 0x9
+                0xFFu8 as i8, // 0x8
+                0x02,         // 0x7: G
+                0xFFu8 as i8, // 0x6
+                u_code,       // 0x5: U
+                t_code,       // 0x4: T
+                0x04,         // 0x3: C
+                0xFFu8 as i8, // 0x2
+                0x08,         // 0x1: A
+                0xFFu8 as i8, // 0x0
+            )
+        }
+    }
+
+    /// Build the companion table of canonical ASCII bytes for each slot of
+    /// [`Self::build_code_lookup`], used to verify that a letter landing in
+    /// a given slot really is that slot's letter (and not, say, an `S`
+    /// colliding with `C`'s low nibble) before trusting the fast-path code.
+    /// Fixed regardless of `ty`, since slots 4 and 5 are `T` and `U`
+    /// specifically, not a slot shared between them.
+    #[cfg(all(target_arch = "x86_64", feature = "simd"))]
+    fn build_canon_lookup() -> core::arch::x86_64::__m128i {
+        use core::arch::x86_64::_mm_set_epi8;
+        unsafe {
+            _mm_set_epi8(
+                0,            // 0xF
+                b'N' as i8,   // 0xE
+                b'-' as i8,   // 0xD
+                0,            // 0xC
+                0,            // 0xB
+                0,            // 0xA
+                0,            // 0x9
+                0,            // 0x8
+                b'G' as i8,   // 0x7
+                0,            // 0x6
+                b'U' as i8,   // 0x5
+                b'T' as i8,   // 0x4
+                b'C' as i8,   // 0x3
+                0,            // 0x2
+                b'A' as i8,   // 0x1
+                0,            // 0x0
+            )
+        }
+    }
+
+    /// Encode 16 ASCII nucleotide letters into 8 packed NAF bytes in one
+    /// pass, or `None` if any of the 16 letters is not handled by the fast
+    /// path (an ambiguity code other than `N`, or invalid data), in which case the
+    /// caller should fall back to [`Self::encode`] for that whole batch.
+    #[cfg(all(target_arch = "x86_64", feature = "simd"))]
+    #[inline]
+    fn encode_simd_batch(&self, inbuf: [u8; 16]) -> Option<[u8; 8]> {
+        use core::arch::x86_64::_mm_and_si128;
+        use core::arch::x86_64::_mm_cmpeq_epi8;
+        use core::arch::x86_64::_mm_loadu_si128;
+        use core::arch::x86_64::_mm_movemask_epi8;
+        use core::arch::x86_64::_mm_set1_epi8;
+        use core::arch::x86_64::_mm_shuffle_epi8;
+        use core::arch::x86_64::_mm_storeu_si128;
+
+        unsafe {
+            let v = _mm_loadu_si128(inbuf.as_ptr().cast());
+            let idx = _mm_and_si128(v, _mm_set1_epi8(0x0F));
+            let code_vec = _mm_shuffle_epi8(self.code_lookup, idx);
+            let canon_vec = _mm_shuffle_epi8(self.canon_lookup, idx);
+
+            // every one of the 16 letters must both land on a known slot
+            // (code_vec != 0xFF) and actually be that slot's letter
+            // (input byte == canon_vec), or we bail out to the slow path
+            let known = _mm_cmpeq_epi8(code_vec, _mm_set1_epi8(0xFFu8 as i8));
+            if _mm_movemask_epi8(known) != 0 {
+                return None;
+            }
+            let matches = _mm_cmpeq_epi8(v, canon_vec);
+            if _mm_movemask_epi8(matches) != 0xFFFF {
+                return None;
+            }
+
+            let mut codes = [0u8; 16];
+            _mm_storeu_si128(codes.as_mut_ptr().cast(), code_vec);
+            let mut packed = [0u8; 8];
+            for i in 0..8 {
+                packed[i] = codes[2 * i] | (codes[2 * i + 1] << 4);
+            }
+            Some(packed)
         }
     }
 }
@@ -74,6 +287,22 @@ impl<W: Write> Write for SequenceWriter<W> {
             bytes = &s[1..];
         }
 
+        // encode whole 16-letter batches through the SIMD fast path first;
+        // a batch containing an ambiguity code or other non-fast-path
+        // letter is left for the scalar loop below to handle instead
+        #[cfg(all(target_arch = "x86_64", feature = "simd"))]
+        while self.simd_capable && bytes.len() >= 16 {
+            let mut inbuf = [0u8; 16];
+            inbuf.copy_from_slice(&bytes[..16]);
+            match self.encode_simd_batch(inbuf) {
+                Some(packed) => {
+                    encoded.extend_from_slice(&packed);
+                    bytes = &bytes[16..];
+                }
+                None => break,
+            }
+        }
+
         for chunk in bytes.chunks(2) {
             if chunk.len() == 1 {
                 assert!(self.cache.is_none());
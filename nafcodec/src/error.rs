@@ -8,6 +8,22 @@ pub enum Error {
     InvalidSequence,
     InvalidLength,
     MissingField(&'static str),
+    /// The archive is encrypted and could not be decoded as requested.
+    Encrypted(&'static str),
+    /// The format of an input stream could not be recognized.
+    UnknownFormat,
+    /// An ASCII-armored stream's framing, encoding, or checksum was invalid.
+    Armor(&'static str),
+    /// More input is needed before parsing can continue.
+    ///
+    /// Carries the number of additional bytes `nom` reports it needs, when
+    /// known, so a caller reading from a non-seekable source (a socket, or
+    /// a `BufReader` that has not filled yet) can request more input and
+    /// retry instead of treating this as a parse failure.
+    Incomplete(Option<usize>),
+    /// A [`Record`](crate::Record) could not be deserialized from, or pushed from, a `serde` stream.
+    #[cfg(feature = "serde")]
+    Serde(String),
 }
 
 impl From<std::io::Error> for Error {
@@ -47,7 +63,8 @@ where
     fn from(error: nom::Err<E>) -> Self {
         match error {
             nom::Err::Error(e) | nom::Err::Failure(e) => e.into(),
-            nom::Err::Incomplete(_) => todo!(),
+            nom::Err::Incomplete(nom::Needed::Unknown) => Error::Incomplete(None),
+            nom::Err::Incomplete(nom::Needed::Size(n)) => Error::Incomplete(Some(n.get())),
         }
     }
 }
@@ -61,6 +78,13 @@ impl std::fmt::Display for Error {
             Error::InvalidLength => f.write_str("inconsistent sequence length"),
             Error::InvalidSequence => f.write_str("invalid character in sequence"),
             Error::MissingField(field) => write!(f, "missing record field: {:?}", field),
+            Error::Encrypted(msg) => f.write_str(msg),
+            Error::UnknownFormat => f.write_str("unrecognized input format"),
+            Error::Armor(msg) => write!(f, "invalid armored stream: {}", msg),
+            Error::Incomplete(Some(n)) => write!(f, "need {} more bytes to continue parsing", n),
+            Error::Incomplete(None) => f.write_str("need more bytes to continue parsing"),
+            #[cfg(feature = "serde")]
+            Error::Serde(msg) => f.write_str(msg),
         }
     }
 }
@@ -74,6 +98,12 @@ impl std::error::Error for Error {
             Error::InvalidLength => None,
             Error::InvalidSequence => None,
             Error::MissingField(_) => None,
+            Error::Encrypted(_) => None,
+            Error::UnknownFormat => None,
+            Error::Armor(_) => None,
+            Error::Incomplete(_) => None,
+            #[cfg(feature = "serde")]
+            Error::Serde(_) => None,
         }
     }
 }
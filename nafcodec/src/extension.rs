@@ -0,0 +1,260 @@
+//! Optional archive-level metadata carried in the `Flag::Extended` block.
+//!
+//! Mirrors the optional fields of a GZIP header (original filename,
+//! modification time, ...): each entry is a typed, length-prefixed field,
+//! so a decoder that does not recognize a tag can still skip over it
+//! using its length prefix and keep reading the fields stored after it,
+//! the same way GZIP's `FEXTRA` sub-fields stay forward-compatible with
+//! readers that predate them.
+
+use std::io::Write;
+
+use crate::error::Error;
+
+const TAG_CREATED_AT: u8 = 1;
+const TAG_PRODUCER: u8 = 2;
+const TAG_SOURCE_FILENAME: u8 = 3;
+const TAG_ANNOTATION: u8 = 4;
+const TAG_ENCRYPTION: u8 = 5;
+
+fn write_varint<W: Write>(mut n: u64, mut w: W) -> std::io::Result<()> {
+    let mut basis = 1;
+    while basis * 128 <= n {
+        basis *= 128;
+    }
+    while basis > 1 {
+        w.write_all(&[((n / basis) | 0x80) as u8])?;
+        n %= basis;
+        basis /= 128;
+    }
+    w.write_all(&[n as u8])
+}
+
+/// Parse a base-128 varint from the front of `buf`, same encoding as
+/// [`write_varint`]. Returns the value and the number of bytes consumed.
+fn read_varint(buf: &[u8]) -> Option<(u64, usize)> {
+    let mut num = 0u64;
+    for (i, &byte) in buf.iter().enumerate() {
+        num = num.checked_mul(128)?.checked_add((byte & 0x7F) as u64)?;
+        if byte & 0x80 == 0 {
+            return Some((num, i + 1));
+        }
+    }
+    None
+}
+
+fn truncated() -> Error {
+    Error::Io(std::io::Error::new(
+        std::io::ErrorKind::UnexpectedEof,
+        "truncated extension field",
+    ))
+}
+
+fn malformed() -> Error {
+    Error::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        "malformed extension field",
+    ))
+}
+
+fn to_utf8(bytes: &[u8]) -> Result<String, Error> {
+    std::str::from_utf8(bytes)
+        .map(String::from)
+        .map_err(Error::from)
+}
+
+/// A single typed field of an [`ExtensionBlock`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExtensionField {
+    /// The time the archive was created, as Unix epoch seconds.
+    CreatedAt(u64),
+    /// The name of the program that produced the archive.
+    Producer(String),
+    /// The name of the file the archive was produced from.
+    SourceFilename(String),
+    /// A free-form UTF-8 annotation.
+    Annotation(String),
+    /// The ephemeral X25519 public key used to encrypt the content blocks.
+    ///
+    /// Stored in cleartext, alongside the compressed fields above: a
+    /// decoder needs this key *before* it can derive the shared secret
+    /// it requires to decrypt anything else, so it cannot itself be
+    /// encrypted. This crate only reads the field: once
+    /// [`EncoderBuilder::recipient_public_key`](crate::EncoderBuilder::recipient_public_key)
+    /// is set, `EncoderBuilder::with_storage` returns
+    /// [`Error::Encrypted`](crate::error::Error::Encrypted) instead of
+    /// writing an archive.
+    Encryption {
+        /// The per-archive ephemeral public key, generated once per archive.
+        ephemeral_public_key: [u8; 32],
+    },
+}
+
+impl ExtensionField {
+    fn tag(&self) -> u8 {
+        match self {
+            ExtensionField::CreatedAt(_) => TAG_CREATED_AT,
+            ExtensionField::Producer(_) => TAG_PRODUCER,
+            ExtensionField::SourceFilename(_) => TAG_SOURCE_FILENAME,
+            ExtensionField::Annotation(_) => TAG_ANNOTATION,
+            ExtensionField::Encryption { .. } => TAG_ENCRYPTION,
+        }
+    }
+
+    fn write<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        w.write_all(&[self.tag()])?;
+        match self {
+            ExtensionField::CreatedAt(timestamp) => {
+                write_varint(8, &mut w)?;
+                w.write_all(&timestamp.to_le_bytes())?;
+            }
+            ExtensionField::Producer(s)
+            | ExtensionField::SourceFilename(s)
+            | ExtensionField::Annotation(s) => {
+                write_varint(s.len() as u64, &mut w)?;
+                w.write_all(s.as_bytes())?;
+            }
+            ExtensionField::Encryption {
+                ephemeral_public_key,
+            } => {
+                write_varint(ephemeral_public_key.len() as u64, &mut w)?;
+                w.write_all(ephemeral_public_key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse a single field from the front of `buf`.
+    ///
+    /// Returns the field (or `None` if `buf` starts with a tag this
+    /// version of the crate does not recognize) together with the number
+    /// of bytes consumed; an unrecognized field is still skipped using
+    /// its length prefix, so the fields after it remain readable.
+    fn read(buf: &[u8]) -> Result<(Option<Self>, usize), Error> {
+        let (&tag, rest) = buf.split_first().ok_or_else(truncated)?;
+        let (len, n) = read_varint(rest).ok_or_else(truncated)?;
+        let len = len as usize;
+        let payload = rest.get(n..n + len).ok_or_else(truncated)?;
+        let consumed = 1 + n + len;
+
+        let field = match tag {
+            TAG_CREATED_AT => {
+                let bytes: [u8; 8] = payload.try_into().map_err(|_| malformed())?;
+                Some(ExtensionField::CreatedAt(u64::from_le_bytes(bytes)))
+            }
+            TAG_PRODUCER => Some(ExtensionField::Producer(to_utf8(payload)?)),
+            TAG_SOURCE_FILENAME => Some(ExtensionField::SourceFilename(to_utf8(payload)?)),
+            TAG_ANNOTATION => Some(ExtensionField::Annotation(to_utf8(payload)?)),
+            TAG_ENCRYPTION => {
+                let ephemeral_public_key: [u8; 32] = payload.try_into().map_err(|_| malformed())?;
+                Some(ExtensionField::Encryption {
+                    ephemeral_public_key,
+                })
+            }
+            _ => None,
+        };
+
+        Ok((field, consumed))
+    }
+}
+
+/// The trailing, optional extension block of a NAF archive.
+///
+/// Exposed through `Flag::Extended`, which used to be reserved and
+/// unused. Built with [`EncoderBuilder`](crate::EncoderBuilder) and read
+/// back with [`Decoder::extensions`](crate::Decoder::extensions).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtensionBlock {
+    fields: Vec<ExtensionField>,
+}
+
+impl ExtensionBlock {
+    /// Create a new, empty extension block.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check whether the extension block has no fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Get every field stored in the extension block, in storage order.
+    pub fn fields(&self) -> &[ExtensionField] {
+        &self.fields
+    }
+
+    /// Append a field to the extension block.
+    pub fn push(&mut self, field: ExtensionField) -> &mut Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Get the archive creation timestamp, in Unix epoch seconds.
+    pub fn created_at(&self) -> Option<u64> {
+        self.fields.iter().find_map(|field| match field {
+            ExtensionField::CreatedAt(timestamp) => Some(*timestamp),
+            _ => None,
+        })
+    }
+
+    /// Get the name of the program that produced the archive.
+    pub fn producer(&self) -> Option<&str> {
+        self.fields.iter().find_map(|field| match field {
+            ExtensionField::Producer(s) => Some(s.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Get the name of the file the archive was produced from.
+    pub fn source_filename(&self) -> Option<&str> {
+        self.fields.iter().find_map(|field| match field {
+            ExtensionField::SourceFilename(s) => Some(s.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Iterate over the free-form annotations stored in the archive.
+    pub fn annotations(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().filter_map(|field| match field {
+            ExtensionField::Annotation(s) => Some(s.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Get the ephemeral public key used to encrypt the archive, if any.
+    ///
+    /// A `Some` return does not mean this build of the crate can decrypt
+    /// the archive: that additionally requires the `crypto` feature and a
+    /// matching private key, see
+    /// [`DecoderBuilder::private_key`](crate::DecoderBuilder::private_key).
+    pub fn encryption(&self) -> Option<[u8; 32]> {
+        self.fields.iter().find_map(|field| match field {
+            ExtensionField::Encryption {
+                ephemeral_public_key,
+            } => Some(*ephemeral_public_key),
+            _ => None,
+        })
+    }
+
+    /// Serialize every field, back to back, into `w`.
+    pub(crate) fn write<W: Write>(&self, mut w: W) -> Result<(), Error> {
+        for field in &self.fields {
+            field.write(&mut w)?;
+        }
+        Ok(())
+    }
+
+    /// Parse every field out of an already-decompressed extension block.
+    pub(crate) fn read(mut buf: &[u8]) -> Result<Self, Error> {
+        let mut fields = Vec::new();
+        while !buf.is_empty() {
+            let (field, consumed) = ExtensionField::read(buf)?;
+            if let Some(field) = field {
+                fields.push(field);
+            }
+            buf = &buf[consumed..];
+        }
+        Ok(Self { fields })
+    }
+}
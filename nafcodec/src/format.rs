@@ -0,0 +1,133 @@
+//! Heuristic detection of the format of an input stream.
+//!
+//! [`detect_format`] only inspects the leading bytes a [`BufRead`] already
+//! has buffered, so it never consumes anything: the bytes it peeked at stay
+//! available for whoever reads from the stream next. This matters for
+//! inputs that cannot be seeked back, such as a pipe or a socket.
+
+use std::io::BufRead;
+
+use crate::error::Error;
+
+const NAF_MAGIC: [u8; 3] = [0x01, 0xF9, 0xEC];
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+const BZIP2_MAGIC: [u8; 3] = [b'B', b'Z', b'h'];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+const XZ_MAGIC: [u8; 6] = [0xFD, b'7', b'z', b'X', b'Z', 0x00];
+const ARMOR_MAGIC: &[u8] = b"-----BEGIN";
+
+/// The format of an input stream, as recognized by [`detect_format`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    /// A Nucleotide Archive Format file.
+    Naf,
+    /// A FASTA file.
+    Fasta,
+    /// A FASTQ file.
+    Fastq,
+    /// A gzip-compressed stream.
+    Gzip,
+    /// A bzip2-compressed stream.
+    Bzip2,
+    /// A Zstandard-compressed stream.
+    Zstd,
+    /// An xz-compressed stream.
+    Xz,
+    /// An ASCII-armored (see [`ArmorReader`](crate::ArmorReader)) text stream.
+    Armored,
+}
+
+/// Detect the format of the data buffered by `reader`.
+///
+/// Returns [`Error::UnknownFormat`] if the leading bytes do not match any of
+/// the recognized compression magic numbers or the `-----BEGIN` armor
+/// delimiter, and the first non-whitespace byte is neither `>` (FASTA) nor
+/// `@` followed by a third line starting with `+` (FASTQ), rather than
+/// guessing.
+pub fn detect_format<R: BufRead>(reader: &mut R) -> Result<InputFormat, Error> {
+    let buf = reader.fill_buf()?;
+
+    if buf.starts_with(&NAF_MAGIC) {
+        return Ok(InputFormat::Naf);
+    }
+    if buf.starts_with(&GZIP_MAGIC) {
+        return Ok(InputFormat::Gzip);
+    }
+    if buf.starts_with(&BZIP2_MAGIC) {
+        return Ok(InputFormat::Bzip2);
+    }
+    if buf.starts_with(&ZSTD_MAGIC) {
+        return Ok(InputFormat::Zstd);
+    }
+    if buf.starts_with(&XZ_MAGIC) {
+        return Ok(InputFormat::Xz);
+    }
+    if buf.starts_with(ARMOR_MAGIC) {
+        return Ok(InputFormat::Armored);
+    }
+
+    match buf.iter().copied().find(|b| !b.is_ascii_whitespace()) {
+        Some(b'>') => Ok(InputFormat::Fasta),
+        Some(b'@') => {
+            let text = std::str::from_utf8(buf).unwrap_or_default();
+            match text.lines().nth(2) {
+                Some(line) if line.starts_with('+') => Ok(InputFormat::Fastq),
+                _ => Err(Error::UnknownFormat),
+            }
+        }
+        _ => Err(Error::UnknownFormat),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_detect_naf() {
+        let mut reader = BufReader::new(Cursor::new([0x01, 0xF9, 0xEC, 0x01]));
+        assert_eq!(detect_format(&mut reader).unwrap(), InputFormat::Naf);
+    }
+
+    #[test]
+    fn test_detect_gzip() {
+        let mut reader = BufReader::new(Cursor::new([0x1F, 0x8B, 0x08, 0x00]));
+        assert_eq!(detect_format(&mut reader).unwrap(), InputFormat::Gzip);
+    }
+
+    #[test]
+    fn test_detect_armor() {
+        let mut reader = BufReader::new(Cursor::new(&b"-----BEGIN NAF ARCHIVE-----\n"[..]));
+        assert_eq!(detect_format(&mut reader).unwrap(), InputFormat::Armored);
+    }
+
+    #[test]
+    fn test_detect_fasta() {
+        let mut reader = BufReader::new(Cursor::new(&b">seq1 example\nACGT\n"[..]));
+        assert_eq!(detect_format(&mut reader).unwrap(), InputFormat::Fasta);
+    }
+
+    #[test]
+    fn test_detect_fastq() {
+        let mut reader = BufReader::new(Cursor::new(&b"@seq1\nACGT\n+\nIIII\n"[..]));
+        assert_eq!(detect_format(&mut reader).unwrap(), InputFormat::Fastq);
+    }
+
+    #[test]
+    fn test_detect_fastq_missing_plus_line() {
+        let mut reader = BufReader::new(Cursor::new(&b"@seq1\nACGT\nIIII\nIIII\n"[..]));
+        assert!(detect_format(&mut reader).is_err());
+    }
+
+    #[test]
+    fn test_detect_unknown() {
+        let mut reader = BufReader::new(Cursor::new(&b"this is not a sequence file"[..]));
+        assert!(matches!(
+            detect_format(&mut reader),
+            Err(Error::UnknownFormat)
+        ));
+    }
+}
@@ -0,0 +1,197 @@
+//! Minimal I/O abstraction allowing this crate to build without `std`.
+//!
+//! This mirrors the approach taken by `zstd-rs`/`ruzstd`: a handful of
+//! `std`-shaped traits are re-declared here, and blanket-implemented for
+//! the real `std::io` traits when the `std` feature (on by default) is
+//! enabled. Code that only needs to read, write or seek through a buffer
+//! can then be written against [`Read`], [`Write`] and [`Seek`] instead of
+//! `std::io::{Read, Write, Seek}`, and keeps working in a `no_std` build.
+//!
+//! Note that the block codecs ([`super::decoder::codec`]) and [`IoSlice`]
+//! still require `std` for now: they sit on top of `std::fs::File`,
+//! `std::sync::RwLock` and the `zstd`/`ruzstd` crates, none of which are
+//! `no_std`-friendly today. Porting those is tracked as follow-up work;
+//! this module only lays the groundwork so that `no_std`-clean pieces of
+//! the crate (such as [`crate::data::Record`]) do not have to depend on
+//! `std` just because the rest of the crate still does.
+
+use alloc::vec::Vec;
+
+/// A `no_std`-friendly stand-in for [`std::io::Error`].
+#[derive(Debug)]
+pub enum Error {
+    /// The end of the underlying source was reached before enough data
+    /// could be read or written.
+    UnexpectedEof,
+    /// The underlying source refused to make progress.
+    WouldBlock,
+    /// Any other failure, carrying a static description.
+    Other(&'static str),
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::UnexpectedEof => {
+                std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "unexpected end of file")
+            }
+            Error::WouldBlock => std::io::Error::new(std::io::ErrorKind::WouldBlock, "would block"),
+            Error::Other(msg) => std::io::Error::new(std::io::ErrorKind::Other, msg),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::UnexpectedEof => Error::UnexpectedEof,
+            std::io::ErrorKind::WouldBlock => Error::WouldBlock,
+            _ => Error::Other("I/O error"),
+        }
+    }
+}
+
+/// A `no_std`-friendly stand-in for [`std::io::Read`].
+pub trait Read {
+    /// Pull some bytes into `buf`, returning how many were read.
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error>;
+
+    /// Read until `buf` is completely filled.
+    fn read_exact(&mut self, mut buf: &mut [u8]) -> Result<(), Error> {
+        while !buf.is_empty() {
+            match self.read(buf)? {
+                0 => return Err(Error::UnexpectedEof),
+                n => buf = &mut buf[n..],
+            }
+        }
+        Ok(())
+    }
+
+    /// Read every remaining byte into `buf`, appending to it.
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize, Error> {
+        let mut chunk = [0u8; 4096];
+        let mut total = 0;
+        loop {
+            match self.read(&mut chunk)? {
+                0 => return Ok(total),
+                n => {
+                    buf.extend_from_slice(&chunk[..n]);
+                    total += n;
+                }
+            }
+        }
+    }
+}
+
+/// A `no_std`-friendly stand-in for [`std::io::Write`].
+pub trait Write {
+    /// Write some bytes from `buf`, returning how many were written.
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error>;
+
+    /// Write the entirety of `buf`.
+    fn write_all(&mut self, mut buf: &[u8]) -> Result<(), Error> {
+        while !buf.is_empty() {
+            match self.write(buf)? {
+                0 => return Err(Error::UnexpectedEof),
+                n => buf = &buf[n..],
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A `no_std`-friendly stand-in for [`std::io::Seek`].
+pub trait Seek {
+    /// Seek to the given byte offset from the start of the stream.
+    fn seek_from_start(&mut self, offset: u64) -> Result<u64, Error>;
+}
+
+/// A `no_std`-friendly stand-in for [`std::io::BufRead`].
+///
+/// This is the trait the block-level readers in [`crate::decoder::reader`]
+/// (`CStringReader`, `LengthReader`, `SequenceReader`, `MaskReader`) would
+/// need to be generic over to drop their `std::io::BufRead` bound.
+/// Rewriting those readers themselves is not done here: their `next()`
+/// methods return `std::io::Error` directly and that error type is
+/// threaded all the way through `Decoder`'s public API, so swapping their
+/// bound for this trait is follow-up work bundled with a matching error
+/// type change, not a drop-in one-line edit.
+pub trait BufRead: Read {
+    /// Return the contents of the internal buffer, filling it first if empty.
+    fn fill_buf(&mut self) -> Result<&[u8], Error>;
+
+    /// Mark `amount` bytes of the buffer returned by `fill_buf` as consumed.
+    fn consume(&mut self, amount: usize);
+
+    /// Read bytes into `buf` until `delimiter` or EOF, including the
+    /// delimiter if found; returns the number of bytes read.
+    fn read_until(&mut self, delimiter: u8, buf: &mut Vec<u8>) -> Result<usize, Error> {
+        let mut read = 0;
+        loop {
+            let (done, used) = {
+                let available = self.fill_buf()?;
+                match available.iter().position(|b| *b == delimiter) {
+                    Some(i) => {
+                        buf.extend_from_slice(&available[..=i]);
+                        (true, i + 1)
+                    }
+                    None => {
+                        buf.extend_from_slice(available);
+                        (false, available.len())
+                    }
+                }
+            };
+            self.consume(used);
+            read += used;
+            if done || used == 0 {
+                return Ok(read);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::BufRead> BufRead for T {
+    fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        std::io::BufRead::fill_buf(self).map_err(Error::from)
+    }
+
+    fn consume(&mut self, amount: usize) {
+        std::io::BufRead::consume(self, amount)
+    }
+
+    fn read_until(&mut self, delimiter: u8, buf: &mut Vec<u8>) -> Result<usize, Error> {
+        std::io::BufRead::read_until(self, delimiter, buf).map_err(Error::from)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::Read> Read for T {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        std::io::Read::read(self, buf).map_err(Error::from)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        std::io::Read::read_exact(self, buf).map_err(Error::from)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::Write> Write for T {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        std::io::Write::write(self, buf).map_err(Error::from)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), Error> {
+        std::io::Write::write_all(self, buf).map_err(Error::from)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::Seek> Seek for T {
+    fn seek_from_start(&mut self, offset: u64) -> Result<u64, Error> {
+        std::io::Seek::seek(self, std::io::SeekFrom::Start(offset)).map_err(Error::from)
+    }
+}
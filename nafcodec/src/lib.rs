@@ -1,28 +1,83 @@
-#![doc = include_str!("../README.md")]
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "std", doc = include_str!("../README.md"))]
 #![cfg_attr(feature = "nightly", feature(seek_stream_len))]
 #![cfg_attr(feature = "nightly", feature(iter_advance_by))]
 
+extern crate alloc;
+
 mod data;
+#[cfg(feature = "std")]
+mod armor;
+#[cfg(all(feature = "std", feature = "crypto"))]
+mod crypto;
+#[cfg(feature = "std")]
 mod decoder;
+#[cfg(feature = "std")]
 mod encoder;
+#[cfg(feature = "std")]
+mod extension;
+#[cfg(feature = "std")]
+mod format;
+mod subsample;
+pub mod io;
 
+#[cfg(feature = "std")]
 pub mod error;
+#[cfg(feature = "std")]
+pub mod writer;
 
+#[cfg(feature = "std")]
+pub use self::armor::ArmorReader;
+#[cfg(feature = "std")]
+pub use self::armor::ArmorWriter;
 pub use self::data::Flag;
 pub use self::data::Flags;
 pub use self::data::FormatVersion;
 pub use self::data::Header;
 pub use self::data::Record;
 pub use self::data::SequenceType;
+#[cfg(all(feature = "std", feature = "async"))]
+pub use self::decoder::AsyncDecoder;
+#[cfg(feature = "std")]
 pub use self::decoder::Decoder;
+#[cfg(feature = "std")]
 pub use self::decoder::DecoderBuilder;
+#[cfg(feature = "std")]
+pub use self::decoder::HeaderDecoder;
+#[cfg(feature = "std")]
+pub use self::decoder::RandomAccessDecoder;
+#[cfg(feature = "std")]
+pub use self::decoder::RecordIndex;
+#[cfg(feature = "std")]
+pub use self::decoder::StreamDecoder;
+#[cfg(feature = "std")]
+pub use self::encoder::train_dictionary;
+#[cfg(feature = "std")]
 pub use self::encoder::Encoder;
+#[cfg(feature = "std")]
 pub use self::encoder::EncoderBuilder;
+#[cfg(feature = "std")]
 pub use self::encoder::Memory;
+#[cfg(feature = "std")]
 pub use self::encoder::Storage;
+#[cfg(feature = "std")]
+pub use self::extension::ExtensionBlock;
+#[cfg(feature = "std")]
+pub use self::extension::ExtensionField;
+#[cfg(feature = "std")]
+pub use self::format::detect_format;
+#[cfg(feature = "std")]
+pub use self::format::InputFormat;
+pub use self::subsample::subsample_to_count;
+pub use self::subsample::subsample_to_coverage;
+pub use self::subsample::subsample_to_fraction;
+#[cfg(feature = "std")]
+pub use self::writer::FastaWriter;
+#[cfg(feature = "std")]
+pub use self::writer::FastqWriter;
 
 /// The reference counter type used to share the stream.
-#[cfg(feature = "arc")]
+#[cfg(all(feature = "std", feature = "arc"))]
 type Rc<T> = std::sync::Arc<T>;
-#[cfg(not(feature = "arc"))]
+#[cfg(all(feature = "std", not(feature = "arc")))]
 type Rc<T> = std::rc::Rc<T>;
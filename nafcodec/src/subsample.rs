@@ -0,0 +1,179 @@
+//! Coverage-based subsampling of decoded records.
+//!
+//! Reproduces the core algorithm behind `rasusa`: given a set of already
+//! decoded [`Record`]s, keep only as many as are needed to approximate a
+//! target sequencing coverage, while preserving their original order. This
+//! operates on a buffered `Vec<Record>` rather than streaming directly off
+//! a [`Decoder`](crate::Decoder), trading memory for simplicity, since
+//! computing the target retained count requires knowing the total number
+//! of bases across every record up front.
+
+use alloc::vec::Vec;
+
+use crate::data::Record;
+
+/// Round a non-negative `f64` to the nearest integer, without `std`.
+///
+/// `core` has no floating-point rounding intrinsics of its own (`f64::round`
+/// lives in `std`, backed by the platform's `libm`), and this module is
+/// `no_std`-compatible (see its `alloc::vec::Vec` import above), so it
+/// cannot call it. Adding `0.5` and truncating towards zero is round-half-up
+/// and only differs from `f64::round` on negative inputs, which never reach
+/// this function: every caller here rounds a record fraction or count.
+fn round_half_up(x: f64) -> f64 {
+    (x + 0.5) as u64 as f64
+}
+
+/// A splitmix64 PRNG, used only to make the draw in this module
+/// reproducible from a seed, without pulling in the `rand` crate for
+/// something this simple.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// A uniform integer in `[0, bound)`.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+/// Subsample `records` to approximate `target_coverage` over a genome of
+/// `genome_size` bases.
+///
+/// Computes the target base count `T = genome_size * target_coverage`. If
+/// the total number of bases already in `records` is at or below `T`, every
+/// record is returned unchanged. Otherwise, the number of records expected
+/// to cover `T` bases is estimated from the average record length, and
+/// [`subsample_to_count`] draws that many. `seed` makes the draw
+/// reproducible.
+pub fn subsample_to_coverage(
+    records: Vec<Record<'static>>,
+    genome_size: u64,
+    target_coverage: f64,
+    seed: u64,
+) -> Vec<Record<'static>> {
+    if records.is_empty() {
+        return records;
+    }
+
+    let target_bases = (genome_size as f64) * target_coverage;
+    let total_bases: u64 = records.iter().map(|r| r.length.unwrap_or(0)).sum();
+    if (total_bases as f64) <= target_bases {
+        return records;
+    }
+
+    let fraction = target_bases / total_bases as f64;
+    let count = round_half_up((records.len() as f64) * fraction).max(1.0) as usize;
+    subsample_to_count(records, count, seed)
+}
+
+/// Subsample `records` down to an explicit `fraction` of its records.
+pub fn subsample_to_fraction(
+    records: Vec<Record<'static>>,
+    fraction: f64,
+    seed: u64,
+) -> Vec<Record<'static>> {
+    let count = round_half_up((records.len() as f64) * fraction) as usize;
+    subsample_to_count(records, count, seed)
+}
+
+/// Subsample `records` down to an explicit number of retained records.
+///
+/// Uses reservoir sampling (Algorithm R): the reservoir starts as the first
+/// `count` records, then for the `i`-th record after that, a uniformly
+/// random slot among the first `i + 1` records is replaced with it. The
+/// result is returned in the original record order, not selection order;
+/// `seed` makes the draw reproducible.
+pub fn subsample_to_count(
+    records: Vec<Record<'static>>,
+    count: usize,
+    seed: u64,
+) -> Vec<Record<'static>> {
+    if records.len() <= count {
+        return records;
+    }
+
+    let mut rng = Rng(seed);
+    let mut reservoir: Vec<usize> = (0..count).collect();
+    for i in count..records.len() {
+        let j = rng.below(i + 1);
+        if j < count {
+            reservoir[j] = i;
+        }
+    }
+    reservoir.sort_unstable();
+
+    let mut kept = Vec::with_capacity(count);
+    let mut slots = reservoir.into_iter().peekable();
+    for (i, record) in records.into_iter().enumerate() {
+        if slots.peek() == Some(&i) {
+            kept.push(record);
+            slots.next();
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(length: u64) -> Record<'static> {
+        Record {
+            length: Some(length),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_subsample_to_count_preserves_order() {
+        let records: Vec<_> = (0..100).map(record).collect();
+        let kept = subsample_to_count(records.clone(), 10, 42);
+        assert_eq!(kept.len(), 10);
+        let mut lengths: Vec<u64> = kept.iter().map(|r| r.length.unwrap()).collect();
+        let mut sorted = lengths.clone();
+        sorted.sort_unstable();
+        assert_eq!(lengths, sorted, "output should preserve original order");
+        lengths.dedup();
+        assert_eq!(lengths.len(), 10, "no record should be selected twice");
+    }
+
+    #[test]
+    fn test_subsample_to_count_is_reproducible() {
+        let records: Vec<_> = (0..50).map(record).collect();
+        let a = subsample_to_count(records.clone(), 5, 1234);
+        let b = subsample_to_count(records, 5, 1234);
+        let lengths_a: Vec<u64> = a.iter().map(|r| r.length.unwrap()).collect();
+        let lengths_b: Vec<u64> = b.iter().map(|r| r.length.unwrap()).collect();
+        assert_eq!(lengths_a, lengths_b);
+    }
+
+    #[test]
+    fn test_subsample_to_count_noop_when_fewer_records_than_target() {
+        let records: Vec<_> = (0..5).map(record).collect();
+        let kept = subsample_to_count(records.clone(), 10, 0);
+        assert_eq!(kept.len(), 5);
+    }
+
+    #[test]
+    fn test_subsample_to_coverage_noop_when_already_below_target() {
+        let records = alloc::vec![record(1000), record(1000)];
+        let kept = subsample_to_coverage(records.clone(), 1000, 10.0, 0);
+        assert_eq!(kept.len(), 2);
+    }
+
+    #[test]
+    fn test_subsample_to_coverage_reduces_above_target() {
+        let records: Vec<_> = (0..1000).map(|_| record(100)).collect();
+        // total bases = 100_000, target = 1_000 * 1.0 = 1_000 bases
+        let kept = subsample_to_coverage(records, 1_000, 1.0, 7);
+        assert!(kept.len() < 1000);
+    }
+}
@@ -0,0 +1,148 @@
+//! Decode-side writers for emitting [`Record`] values as FASTA or FASTQ text.
+//!
+//! These complement the encoder's [`SequenceWriter`](crate::encoder), which
+//! only handles the NAF direction; `FastaWriter` and `FastqWriter` close the
+//! loop by turning decoded records back into wrapped plain-text output.
+
+use std::io::Write;
+
+use crate::data::Record;
+use crate::error::Error;
+
+/// The default line length used when none is given, matching classic FASTA.
+const DEFAULT_LINE_LENGTH: usize = 60;
+
+/// Write `sequence`, hard-wrapped at `line_length` columns, to `writer`.
+///
+/// A `line_length` of `0` disables wrapping and writes the whole sequence
+/// on a single line.
+fn write_wrapped<W: Write>(writer: &mut W, sequence: &str, line_length: usize) -> Result<(), Error> {
+    let bytes = sequence.as_bytes();
+    if line_length == 0 {
+        writer.write_all(bytes)?;
+        writer.write_all(b"\n")?;
+        return Ok(());
+    }
+    for chunk in bytes.chunks(line_length) {
+        writer.write_all(chunk)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// A writer for emitting decoded [`Record`] values in FASTA format.
+///
+/// ```
+/// # use nafcodec::{Decoder, writer::FastaWriter};
+/// let decoder = Decoder::from_path("../data/phix.naf").unwrap();
+/// let mut writer = FastaWriter::new(Vec::new(), decoder.header().line_length() as usize);
+/// for record in decoder.map(Result::unwrap) {
+///     writer.write_record(&record).unwrap();
+/// }
+/// ```
+pub struct FastaWriter<W: Write> {
+    writer: W,
+    line_length: usize,
+}
+
+impl<W: Write> FastaWriter<W> {
+    /// Create a new FASTA writer wrapping sequences at `line_length` columns.
+    ///
+    /// Pass the archive's [`Header::line_length`](crate::Header::line_length)
+    /// to reproduce the original wrapping, or `0` to disable wrapping.
+    pub fn new(writer: W, line_length: usize) -> Self {
+        Self { writer, line_length }
+    }
+
+    /// Create a new FASTA writer using the default 60-column wrapping.
+    pub fn with_defaults(writer: W) -> Self {
+        Self::new(writer, DEFAULT_LINE_LENGTH)
+    }
+
+    /// Write a single record to the underlying writer.
+    pub fn write_record(&mut self, record: &Record) -> Result<(), Error> {
+        let id = record.id.as_deref().ok_or(Error::MissingField("id"))?;
+        let sequence = record
+            .sequence
+            .as_deref()
+            .ok_or(Error::MissingField("sequence"))?;
+
+        self.writer.write_all(b">")?;
+        self.writer.write_all(id.as_bytes())?;
+        if let Some(comment) = record.comment.as_deref() {
+            self.writer.write_all(b" ")?;
+            self.writer.write_all(comment.as_bytes())?;
+        }
+        self.writer.write_all(b"\n")?;
+        write_wrapped(&mut self.writer, sequence, self.line_length)?;
+
+        Ok(())
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush().map_err(Error::from)
+    }
+
+    /// Extract the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// A writer for emitting decoded [`Record`] values in FASTQ format.
+pub struct FastqWriter<W: Write> {
+    writer: W,
+    line_length: usize,
+}
+
+impl<W: Write> FastqWriter<W> {
+    /// Create a new FASTQ writer wrapping sequence and quality lines.
+    ///
+    /// Pass the archive's [`Header::line_length`](crate::Header::line_length)
+    /// to reproduce the original wrapping, or `0` to disable wrapping.
+    pub fn new(writer: W, line_length: usize) -> Self {
+        Self { writer, line_length }
+    }
+
+    /// Create a new FASTQ writer using the default 60-column wrapping.
+    pub fn with_defaults(writer: W) -> Self {
+        Self::new(writer, DEFAULT_LINE_LENGTH)
+    }
+
+    /// Write a single record to the underlying writer.
+    pub fn write_record(&mut self, record: &Record) -> Result<(), Error> {
+        let id = record.id.as_deref().ok_or(Error::MissingField("id"))?;
+        let sequence = record
+            .sequence
+            .as_deref()
+            .ok_or(Error::MissingField("sequence"))?;
+        let quality = record
+            .quality
+            .as_deref()
+            .ok_or(Error::MissingField("quality"))?;
+
+        self.writer.write_all(b"@")?;
+        self.writer.write_all(id.as_bytes())?;
+        if let Some(comment) = record.comment.as_deref() {
+            self.writer.write_all(b" ")?;
+            self.writer.write_all(comment.as_bytes())?;
+        }
+        self.writer.write_all(b"\n")?;
+        write_wrapped(&mut self.writer, sequence, self.line_length)?;
+        self.writer.write_all(b"+\n")?;
+        write_wrapped(&mut self.writer, quality, self.line_length)?;
+
+        Ok(())
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush().map_err(Error::from)
+    }
+
+    /// Extract the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}